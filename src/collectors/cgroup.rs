@@ -0,0 +1,152 @@
+//! cgroup v2 helpers shared by the cgroup-aware collectors.
+
+use crate::errors::CollectorError;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Where the agent finds its own cgroup: the cgroup2 mount it sees and its
+/// own /proc/self/cgroup. Both are the agent's view, never HOST_SYS or
+/// HOST_PROC, which point at the host.
+#[derive(Debug, Clone)]
+pub(crate) struct OwnCgroup {
+    pub(crate) mount: PathBuf,
+    pub(crate) self_cgroup: PathBuf,
+}
+
+impl Default for OwnCgroup {
+    fn default() -> Self {
+        Self {
+            mount: PathBuf::from("/sys/fs/cgroup"),
+            self_cgroup: PathBuf::from("/proc/self/cgroup"),
+        }
+    }
+}
+
+impl OwnCgroup {
+    /// Directory of the agent's own cgroup. On a host this is e.g.
+    /// /sys/fs/cgroup/system.slice/agent.service, not the root cgroup; inside
+    /// a cgroup namespace /proc/self/cgroup reads `0::/` and it is the mount
+    /// itself. Without a unified entry (cgroup v1) the mount is returned and
+    /// the v2 files are simply not found.
+    pub(crate) async fn dir(&self) -> Result<PathBuf, CollectorError> {
+        let content = fs::read_to_string(&self.self_cgroup).await.map_err(|e| {
+            CollectorError::ProcReadError {
+                path: self.self_cgroup.display().to_string(),
+                source: e,
+            }
+        })?;
+        Ok(match parse_self_cgroup(&content) {
+            Some(path) => self.mount.join(path.trim_start_matches('/')),
+            None => self.mount.clone(),
+        })
+    }
+}
+
+/// The unified hierarchy entry (`0::<path>`) of /proc/self/cgroup.
+/// Absent on pure cgroup v1 hosts.
+pub(crate) fn parse_self_cgroup(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().to_string())
+}
+
+/// Directory of the monitored cgroup: a configured path under the cgroup2
+/// mount at <sys_root>/fs/cgroup, or the agent's own cgroup.
+pub(crate) async fn monitored_dir(
+    sys_root: &Path,
+    own: &OwnCgroup,
+    configured: Option<&str>,
+) -> Result<PathBuf, CollectorError> {
+    match configured {
+        Some(cgroup) => Ok(sys_root
+            .join("fs/cgroup")
+            .join(cgroup.trim_start_matches('/'))),
+        None => own.dir().await,
+    }
+}
+
+/// Read a cgroup file, `Ok(None)` if it does not exist (cgroup v1, the
+/// controller is not enabled for this cgroup, or it is the root cgroup).
+pub(crate) async fn read_optional(path: &Path) -> Result<Option<String>, CollectorError> {
+    match fs::read_to_string(path).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(CollectorError::ProcReadError {
+            path: path.display().to_string(),
+            source: e,
+        }),
+    }
+}
+
+/// Parse a single-value limit file (memory.max, memory.high): bytes, or
+/// `max` when unlimited.
+pub(crate) fn parse_limit(path: &str, content: &str) -> Result<Option<u64>, CollectorError> {
+    match content.trim() {
+        "max" => Ok(None),
+        value => value
+            .parse()
+            .map(Some)
+            .map_err(|_| CollectorError::ParseError {
+                path: path.into(),
+                field: "limit".into(),
+                raw: value.into(),
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_self_cgroup_prefers_unified_entry() {
+        let content = "12:memory:/docker/abc\n0::/system.slice/docker-abc.scope\n";
+        assert_eq!(
+            parse_self_cgroup(content).as_deref(),
+            Some("/system.slice/docker-abc.scope")
+        );
+        assert_eq!(parse_self_cgroup("4:cpu:/\n"), None);
+    }
+
+    #[tokio::test]
+    async fn test_monitored_dir_applies_sys_root_to_configured_cgroup_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let own = OwnCgroup {
+            mount: PathBuf::from("/sys/fs/cgroup"),
+            self_cgroup: dir.path().join("cgroup"),
+        };
+        std::fs::write(&own.self_cgroup, "0::/system.slice/agent.service\n").unwrap();
+        assert_eq!(
+            monitored_dir(
+                Path::new("/host/sys"),
+                &own,
+                Some("/system.slice/mysql.service")
+            )
+            .await
+            .unwrap(),
+            Path::new("/host/sys/fs/cgroup/system.slice/mysql.service")
+        );
+        // on a host the agent's own cgroup is below the root cgroup
+        assert_eq!(
+            monitored_dir(Path::new("/host/sys"), &own, None)
+                .await
+                .unwrap(),
+            Path::new("/sys/fs/cgroup/system.slice/agent.service")
+        );
+        // inside a cgroup namespace it is the mount itself
+        std::fs::write(&own.self_cgroup, "0::/\n").unwrap();
+        assert_eq!(own.dir().await.unwrap(), Path::new("/sys/fs/cgroup"));
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("memory.max", "max\n").unwrap(), None);
+        assert_eq!(
+            parse_limit("memory.max", "1073741824\n").unwrap(),
+            Some(1 << 30)
+        );
+        assert!(parse_limit("memory.max", "1G").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use super::{cgroup, CgroupCpuSnapshot, CheckStatus, CollectionResult, Collector, MetricPayload};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Share of enforcement periods in which the cgroup was throttled.
+const THROTTLED_PERIODS_DEGRADED_PCT: f64 = 5.0;
+const THROTTLED_PERIODS_UNHEALTHY_PCT: f64 = 25.0;
+/// Usage as a share of the quota; sustained use near the cap means the
+/// next burst gets throttled.
+const QUOTA_USAGE_DEGRADED_PCT: f64 = 90.0;
+
+/// cgroup v2 CPU accounting for the agent's own cgroup or a configured one,
+/// reading cpu.max and cpu.stat.
+pub struct CgroupCpuCollector {
+    sys_root: PathBuf,
+    own: cgroup::OwnCgroup,
+    cgroup: Option<String>,
+    prev: Option<(Instant, CpuStat)>,
+}
+
+/// Counters from cpu.stat that the collector uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CpuStat {
+    usage_usec: u64,
+    nr_periods: u64,
+    nr_throttled: u64,
+    throttled_usec: u64,
+}
+
+impl CgroupCpuCollector {
+    /// `cgroup` is a path relative to the cgroup2 mount under `sys_root`
+    /// (e.g. `/system.slice/mysql.service`); `None` means the agent's own
+    /// cgroup, found through /proc/self/cgroup under /sys/fs/cgroup.
+    pub fn new(sys_root: impl Into<PathBuf>, cgroup: Option<String>) -> Self {
+        Self {
+            sys_root: sys_root.into(),
+            own: cgroup::OwnCgroup::default(),
+            cgroup,
+            prev: None,
+        }
+    }
+
+    /// Parse cpu.max: `<quota> <period>` in microseconds, quota `max` when
+    /// unlimited. Returns the quota in cores.
+    fn parse_cpu_max(path: &str, content: &str) -> Result<Option<f64>, CollectorError> {
+        let err = || CollectorError::ParseError {
+            path: path.into(),
+            field: "cpu.max".into(),
+            raw: content.trim().to_string(),
+        };
+        let mut parts = content.split_whitespace();
+        let quota = parts.next().ok_or_else(err)?;
+        let period: u64 = match parts.next() {
+            Some(p) => p.parse().map_err(|_| err())?,
+            None => 100_000,
+        };
+        if quota == "max" {
+            return Ok(None);
+        }
+        let quota: u64 = quota.parse().map_err(|_| err())?;
+        if period == 0 {
+            return Err(err());
+        }
+        Ok(Some(quota as f64 / period as f64))
+    }
+
+    fn parse_cpu_stat(path: &str, content: &str) -> Result<CpuStat, CollectorError> {
+        let mut stat = CpuStat::default();
+        let mut seen_usage = false;
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            let slot = match key {
+                "usage_usec" => {
+                    seen_usage = true;
+                    &mut stat.usage_usec
+                }
+                "nr_periods" => &mut stat.nr_periods,
+                "nr_throttled" => &mut stat.nr_throttled,
+                "throttled_usec" => &mut stat.throttled_usec,
+                _ => continue,
+            };
+            *slot = value
+                .trim()
+                .parse()
+                .map_err(|_| CollectorError::ParseError {
+                    path: path.into(),
+                    field: key.into(),
+                    raw: line.to_string(),
+                })?;
+        }
+        if !seen_usage {
+            return Err(CollectorError::ParseError {
+                path: path.into(),
+                field: "usage_usec".into(),
+                raw: content.to_string(),
+            });
+        }
+        Ok(stat)
+    }
+
+    fn evaluate(snapshot: &CgroupCpuSnapshot) -> CheckStatus {
+        let throttled = snapshot.throttled_periods_pct.unwrap_or(0.0);
+        if throttled >= THROTTLED_PERIODS_UNHEALTHY_PCT {
+            CheckStatus::Unhealthy
+        } else if throttled >= THROTTLED_PERIODS_DEGRADED_PCT
+            || snapshot
+                .usage_pct_of_quota
+                .is_some_and(|pct| pct >= QUOTA_USAGE_DEGRADED_PCT)
+        {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+
+    fn unavailable(&self, cgroup: Option<String>, reason: &str) -> CollectionResult {
+        CollectionResult {
+            check_name: self.name().to_string(),
+            status: CheckStatus::Healthy,
+            message: reason.to_string(),
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::CgroupCpu(CgroupCpuSnapshot {
+                cgroup,
+                ..CgroupCpuSnapshot::default()
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for CgroupCpuCollector {
+    fn name(&self) -> &'static str {
+        "cgroup_cpu"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let cgroup = self.cgroup.clone();
+        let dir = cgroup::monitored_dir(&self.sys_root, &self.own, cgroup.as_deref()).await?;
+        // cgroup v1 has no cpu.stat at the top of the mount
+        let stat_path = dir.join("cpu.stat");
+        let Some(stat_raw) = cgroup::read_optional(&stat_path).await? else {
+            return Ok(self.unavailable(cgroup, "cgroup v2 cpu.stat not available"));
+        };
+        let stat = Self::parse_cpu_stat(&stat_path.display().to_string(), &stat_raw)?;
+
+        // the root cgroup has no cpu.max
+        let max_path = dir.join("cpu.max");
+        let quota_cores = match cgroup::read_optional(&max_path).await? {
+            Some(raw) => Self::parse_cpu_max(&max_path.display().to_string(), &raw)?,
+            None => None,
+        };
+
+        let now = Instant::now();
+        let mut snapshot = CgroupCpuSnapshot {
+            available: true,
+            cgroup,
+            quota_cores,
+            nr_periods: stat.nr_periods,
+            nr_throttled: stat.nr_throttled,
+            throttled_usec: stat.throttled_usec,
+            ..CgroupCpuSnapshot::default()
+        };
+        if let Some((prev_at, prev)) = self.prev {
+            let elapsed_us = now.duration_since(prev_at).as_micros() as f64;
+            // counters only go backwards if the cgroup was recreated
+            if elapsed_us > 0.0 && stat.usage_usec >= prev.usage_usec {
+                let usage_cores = (stat.usage_usec - prev.usage_usec) as f64 / elapsed_us;
+                snapshot.usage_cores = Some(usage_cores);
+                snapshot.usage_pct_of_quota = quota_cores.map(|q| usage_cores / q * 100.0);
+                let periods = stat.nr_periods.saturating_sub(prev.nr_periods);
+                if periods > 0 {
+                    let throttled = stat.nr_throttled.saturating_sub(prev.nr_throttled);
+                    snapshot.throttled_periods_pct =
+                        Some(throttled as f64 / periods as f64 * 100.0);
+                }
+                snapshot.throttled_usec_delta =
+                    Some(stat.throttled_usec.saturating_sub(prev.throttled_usec));
+            }
+        }
+        self.prev = Some((now, stat));
+
+        let status = Self::evaluate(&snapshot);
+        let message = format!(
+            "cgroup={} quota={} usage={} throttled_periods={}",
+            snapshot.cgroup.as_deref().unwrap_or("self"),
+            quota_cores.map_or("max".to_string(), |q| format!("{q:.2} cores")),
+            match (snapshot.usage_cores, snapshot.usage_pct_of_quota) {
+                (Some(cores), Some(pct)) => format!("{cores:.2} cores ({pct:.1}% of quota)"),
+                (Some(cores), None) => format!("{cores:.2} cores"),
+                _ => "n/a".to_string(),
+            },
+            snapshot
+                .throttled_periods_pct
+                .map_or("n/a".to_string(), |pct| format!("{pct:.1}%")),
+        );
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::CgroupCpu(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const SAMPLE_CPU_STAT: &str = "\
+usage_usec 8000000
+user_usec 6000000
+system_usec 2000000
+nr_periods 100
+nr_throttled 10
+throttled_usec 500000
+nr_bursts 0
+burst_usec 0";
+
+    fn write_cgroup(dir: &Path, cpu_max: Option<&str>, cpu_stat: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        if let Some(max) = cpu_max {
+            std::fs::write(dir.join("cpu.max"), max).unwrap();
+        }
+        std::fs::write(dir.join("cpu.stat"), cpu_stat).unwrap();
+    }
+
+    /// The agent's view: a cgroup2 mount at `root/mount` and a
+    /// /proc/self/cgroup placing it in `path`.
+    fn own_cgroup(root: &Path, path: &str) -> cgroup::OwnCgroup {
+        let own = cgroup::OwnCgroup {
+            mount: root.join("mount"),
+            self_cgroup: root.join("self-cgroup"),
+        };
+        std::fs::write(&own.self_cgroup, format!("0::{path}\n")).unwrap();
+        own
+    }
+
+    #[test]
+    fn test_parse_cpu_max() {
+        let q = CgroupCpuCollector::parse_cpu_max("cpu.max", "200000 100000\n").unwrap();
+        assert_eq!(q, Some(2.0));
+        assert_eq!(
+            CgroupCpuCollector::parse_cpu_max("cpu.max", "max 100000\n").unwrap(),
+            None
+        );
+        assert!(CgroupCpuCollector::parse_cpu_max("cpu.max", "lots 100000").is_err());
+    }
+
+    #[test]
+    fn test_parse_cpu_stat() {
+        let s = CgroupCpuCollector::parse_cpu_stat("cpu.stat", SAMPLE_CPU_STAT).unwrap();
+        assert_eq!(s.usage_usec, 8_000_000);
+        assert_eq!(s.nr_periods, 100);
+        assert_eq!(s.nr_throttled, 10);
+        assert_eq!(s.throttled_usec, 500_000);
+        assert!(CgroupCpuCollector::parse_cpu_stat("cpu.stat", "nr_periods 1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_own_cgroup_throttling_between_collections() {
+        let dir = tempfile::tempdir().unwrap();
+        let own_cgroup = own_cgroup(dir.path(), "/system.slice/agent.service");
+        let own = own_cgroup.mount.join("system.slice/agent.service");
+        write_cgroup(&own, Some("200000 100000"), SAMPLE_CPU_STAT);
+        // the root cgroup accounts for the whole host and has no quota
+        write_cgroup(
+            &own_cgroup.mount,
+            None,
+            "usage_usec 99000000\nnr_periods 0\nnr_throttled 0\nthrottled_usec 0\n",
+        );
+        // HOST_SYS points elsewhere; the own cgroup must not be looked up there
+        let mut collector = CgroupCpuCollector::new(dir.path().join("host-sys"), None);
+        collector.own = own_cgroup;
+        let first = collector.collect().await.unwrap();
+        assert_eq!(first.status, CheckStatus::Healthy);
+
+        // 40 of the next 100 periods were throttled
+        std::fs::write(
+            own.join("cpu.stat"),
+            "usage_usec 8000100\nnr_periods 200\nnr_throttled 50\nthrottled_usec 900000\n",
+        )
+        .unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        match result.payload {
+            MetricPayload::CgroupCpu(s) => {
+                assert!(s.available);
+                assert_eq!(s.cgroup, None);
+                assert_eq!(s.quota_cores, Some(2.0));
+                assert_eq!(s.throttled_periods_pct, Some(40.0));
+                assert_eq!(s.throttled_usec_delta, Some(400_000));
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_configured_cgroup_without_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cgroup(
+            &dir.path().join("fs/cgroup/docker/abc"),
+            None,
+            SAMPLE_CPU_STAT,
+        );
+        let mut collector = CgroupCpuCollector::new(dir.path(), Some("/docker/abc".into()));
+        collector.own = own_cgroup(dir.path(), "/missing");
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("cgroup=/docker/abc"));
+        assert!(result.message.contains("quota=max"));
+    }
+
+    #[tokio::test]
+    async fn test_cgroup_v1_host() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("mount/cpu,cpuacct")).unwrap();
+        let mut collector = CgroupCpuCollector::new(dir.path(), None);
+        collector.own = cgroup::OwnCgroup {
+            mount: dir.path().join("mount"),
+            self_cgroup: dir.path().join("self-cgroup"),
+        };
+        std::fs::write(&collector.own.self_cgroup, "4:cpu,cpuacct:/\n").unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::CgroupCpu(s) => assert!(!s.available),
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use super::vmstat::VmstatCollector;
+use super::{
+    cgroup, CgroupMemoryEvents, CgroupMemorySnapshot, CheckStatus, CollectionResult, Collector,
+    MetricPayload,
+};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Working set (usage minus inactive page cache) as a share of the limit.
+const WORKING_SET_DEGRADED_PCT: f64 = 90.0;
+
+/// cgroup v2 memory collector for the agent's own cgroup or a configured
+/// one (a systemd service or container), reading memory.current, memory.max,
+/// memory.high, memory.events and memory.stat.
+pub struct CgroupMemoryCollector {
+    sys_root: PathBuf,
+    own: cgroup::OwnCgroup,
+    cgroup: Option<String>,
+    prev_events: Option<CgroupMemoryEvents>,
+}
+
+impl CgroupMemoryCollector {
+    /// `cgroup` is a path relative to the cgroup2 mount under `sys_root`
+    /// (e.g. `/system.slice/mysql.service`); `None` means the agent's own
+    /// cgroup, found through /proc/self/cgroup under /sys/fs/cgroup.
+    pub fn new(sys_root: impl Into<PathBuf>, cgroup: Option<String>) -> Self {
+        Self {
+            sys_root: sys_root.into(),
+            own: cgroup::OwnCgroup::default(),
+            cgroup,
+            prev_events: None,
+        }
+    }
+
+    async fn read_map(dir: &Path, file: &str) -> Result<HashMap<String, u64>, CollectorError> {
+        let path = dir.join(file);
+        match cgroup::read_optional(&path).await? {
+            Some(content) => VmstatCollector::parse_vmstat(&path.display().to_string(), &content),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    async fn read_limit(dir: &Path, file: &str) -> Result<Option<u64>, CollectorError> {
+        let path = dir.join(file);
+        match cgroup::read_optional(&path).await? {
+            Some(content) => cgroup::parse_limit(&path.display().to_string(), &content),
+            None => Ok(None),
+        }
+    }
+
+    fn events(map: &HashMap<String, u64>) -> CgroupMemoryEvents {
+        let get = |key: &str| map.get(key).copied().unwrap_or(0);
+        CgroupMemoryEvents {
+            low: get("low"),
+            high: get("high"),
+            max: get("max"),
+            oom: get("oom"),
+            oom_kill: get("oom_kill"),
+        }
+    }
+
+    /// Events since the previous sample. A counter going backwards means
+    /// the cgroup was recreated (service restart), so its total counts.
+    fn events_delta(prev: &CgroupMemoryEvents, current: &CgroupMemoryEvents) -> CgroupMemoryEvents {
+        let delta = |c: u64, p: u64| if c >= p { c - p } else { c };
+        CgroupMemoryEvents {
+            low: delta(current.low, prev.low),
+            high: delta(current.high, prev.high),
+            max: delta(current.max, prev.max),
+            oom: delta(current.oom, prev.oom),
+            oom_kill: delta(current.oom_kill, prev.oom_kill),
+        }
+    }
+
+    fn evaluate(snapshot: &CgroupMemorySnapshot) -> CheckStatus {
+        let events = snapshot.events_since_last.as_ref();
+        if events.is_some_and(|e| e.oom > 0 || e.oom_kill > 0) {
+            CheckStatus::Unhealthy
+        } else if events.is_some_and(|e| e.max > 0 || e.high > 0)
+            || snapshot
+                .working_set_pct_of_limit
+                .is_some_and(|pct| pct >= WORKING_SET_DEGRADED_PCT)
+        {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+
+    fn unavailable(&self, cgroup: Option<String>, reason: &str) -> CollectionResult {
+        CollectionResult {
+            check_name: self.name().to_string(),
+            status: CheckStatus::Healthy,
+            message: reason.to_string(),
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::CgroupMemory(CgroupMemorySnapshot {
+                cgroup,
+                ..CgroupMemorySnapshot::default()
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for CgroupMemoryCollector {
+    fn name(&self) -> &'static str {
+        "cgroup_memory"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let cgroup = self.cgroup.clone();
+        let dir = cgroup::monitored_dir(&self.sys_root, &self.own, cgroup.as_deref()).await?;
+        // cgroup v1 has no memory.current at the top of the mount
+        let current_path = dir.join("memory.current");
+        let Some(current) = cgroup::read_optional(&current_path).await? else {
+            return Ok(self.unavailable(cgroup, "cgroup v2 memory.current not available"));
+        };
+        let current_bytes: u64 =
+            current
+                .trim()
+                .parse()
+                .map_err(|_| CollectorError::ParseError {
+                    path: current_path.display().to_string(),
+                    field: "memory.current".into(),
+                    raw: current.trim().to_string(),
+                })?;
+
+        let max_bytes = Self::read_limit(&dir, "memory.max").await?;
+        let high_bytes = Self::read_limit(&dir, "memory.high").await?;
+        let stat = Self::read_map(&dir, "memory.stat").await?;
+        let events = Self::events(&Self::read_map(&dir, "memory.events").await?);
+
+        // inactive file pages are reclaimed before the limit bites
+        let working_set_bytes =
+            current_bytes.saturating_sub(stat.get("inactive_file").copied().unwrap_or(0));
+        let limit = match (max_bytes, high_bytes) {
+            (Some(max), Some(high)) => Some(max.min(high)),
+            (max, high) => max.or(high),
+        };
+        let snapshot = CgroupMemorySnapshot {
+            available: true,
+            cgroup,
+            current_bytes,
+            working_set_bytes,
+            max_bytes,
+            high_bytes,
+            working_set_pct_of_limit: limit
+                .filter(|l| *l > 0)
+                .map(|l| working_set_bytes as f64 / l as f64 * 100.0),
+            anon_bytes: stat.get("anon").copied(),
+            file_bytes: stat.get("file").copied(),
+            shmem_bytes: stat.get("shmem").copied(),
+            slab_bytes: stat.get("slab").copied(),
+            sock_bytes: stat.get("sock").copied(),
+            file_dirty_bytes: stat.get("file_dirty").copied(),
+            events_since_last: self
+                .prev_events
+                .as_ref()
+                .map(|prev| Self::events_delta(prev, &events)),
+            events,
+        };
+        self.prev_events = Some(snapshot.events.clone());
+
+        let status = Self::evaluate(&snapshot);
+        let mb = |b: u64| b / (1024 * 1024);
+        let mut message = format!(
+            "cgroup={} working_set={} MB current={} MB limit={}",
+            snapshot.cgroup.as_deref().unwrap_or("self"),
+            mb(working_set_bytes),
+            mb(current_bytes),
+            match (limit, snapshot.working_set_pct_of_limit) {
+                (Some(l), Some(pct)) => format!("{} MB ({pct:.1}% used)", mb(l)),
+                _ => "max".to_string(),
+            }
+        );
+        if let Some(e) = &snapshot.events_since_last {
+            message.push_str(&format!(
+                " events: high={} max={} oom={} oom_kill={}",
+                e.high, e.max, e.oom, e.oom_kill
+            ));
+        }
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::CgroupMemory(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1 << 30;
+
+    fn events(high: u64, oom_kill: u64) -> String {
+        format!("low 0\nhigh {high}\nmax 0\noom 0\noom_kill {oom_kill}\n")
+    }
+
+    #[test]
+    fn test_events_delta_after_cgroup_recreated() {
+        let prev = CgroupMemoryEvents {
+            high: 10,
+            oom_kill: 2,
+            ..CgroupMemoryEvents::default()
+        };
+        let current = CgroupMemoryEvents {
+            high: 3,
+            oom_kill: 2,
+            ..CgroupMemoryEvents::default()
+        };
+        let delta = CgroupMemoryCollector::events_delta(&prev, &current);
+        assert_eq!(delta.high, 3);
+        assert_eq!(delta.oom_kill, 0);
+    }
+
+    #[tokio::test]
+    async fn test_usage_against_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cg = dir.path().join("fs/cgroup/system.slice/mysql.service");
+        std::fs::create_dir_all(&cg).unwrap();
+        std::fs::write(cg.join("memory.current"), format!("{}\n", 15 * GIB / 2)).unwrap();
+        std::fs::write(cg.join("memory.max"), format!("{}\n", 8 * GIB)).unwrap();
+        std::fs::write(cg.join("memory.high"), "max\n").unwrap();
+        std::fs::write(
+            cg.join("memory.stat"),
+            format!(
+                "anon {}\nfile {}\ninactive_file {}\n",
+                6 * GIB,
+                GIB,
+                GIB / 2
+            ),
+        )
+        .unwrap();
+        std::fs::write(cg.join("memory.events"), events(0, 0)).unwrap();
+
+        let mut collector =
+            CgroupMemoryCollector::new(dir.path(), Some("/system.slice/mysql.service".into()));
+        let result = collector.collect().await.unwrap();
+        // working set 7 GiB of 8 GiB
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::CgroupMemory(s) => {
+                assert_eq!(s.working_set_bytes, 7 * GIB);
+                assert_eq!(s.max_bytes, Some(8 * GIB));
+                assert_eq!(s.high_bytes, None);
+                assert_eq!(s.working_set_pct_of_limit, Some(87.5));
+                assert_eq!(s.anon_bytes, Some(6 * GIB));
+                assert!(s.events_since_last.is_none());
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_turn_into_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let cg = dir.path().join("fs/cgroup/system.slice/mysql.service");
+        std::fs::create_dir_all(&cg).unwrap();
+        std::fs::write(cg.join("memory.current"), "1048576\n").unwrap();
+        std::fs::write(cg.join("memory.max"), "max\n").unwrap();
+        std::fs::write(cg.join("memory.events"), events(0, 0)).unwrap();
+        let mut collector =
+            CgroupMemoryCollector::new(dir.path(), Some("/system.slice/mysql.service".into()));
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Healthy
+        );
+
+        std::fs::write(cg.join("memory.events"), events(5, 0)).unwrap();
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Degraded
+        );
+
+        std::fs::write(cg.join("memory.events"), events(5, 1)).unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        assert!(result.message.contains("oom_kill=1"));
+    }
+
+    #[tokio::test]
+    async fn test_cgroup_without_memory_controller() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("fs/cgroup/system.slice/mysql.service")).unwrap();
+        let mut collector =
+            CgroupMemoryCollector::new(dir.path(), Some("/system.slice/mysql.service".into()));
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("not available"));
+    }
+
+    #[tokio::test]
+    async fn test_own_cgroup_ignores_sys_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let mount = dir.path().join("mount");
+        let own = mount.join("system.slice/agent.service");
+        std::fs::create_dir_all(&own).unwrap();
+        std::fs::write(own.join("memory.current"), "1048576\n").unwrap();
+        std::fs::write(own.join("memory.max"), "2097152\n").unwrap();
+        // the root cgroup has no memory.current, so reading it would report
+        // "not available"
+        let self_cgroup = dir.path().join("self-cgroup");
+        std::fs::write(&self_cgroup, "0::/system.slice/agent.service\n").unwrap();
+        let mut collector = CgroupMemoryCollector::new(dir.path().join("host-sys"), None);
+        collector.own = cgroup::OwnCgroup { mount, self_cgroup };
+        let result = collector.collect().await.unwrap();
+        assert!(result.message.contains("cgroup=self"));
+        match result.payload {
+            MetricPayload::CgroupMemory(s) => {
+                assert!(s.available);
+                assert_eq!(s.cgroup, None);
+                assert_eq!(s.max_bytes, Some(2097152));
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
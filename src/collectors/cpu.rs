@@ -1,12 +1,111 @@
 use super::*;
 use crate::errors::CollectorError;
 use async_trait::async_trait;
+use nix::unistd::{sysconf, SysconfVar};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Instant;
 use tokio::fs;
 
+/// A single core this busy while the aggregate stays below
+/// `HOT_CORE_MAX_AGGREGATE_BUSY_PCT` is reported as a hot core — the usual
+/// signature of a single-threaded bottleneck (replication SQL thread, purge).
+const HOT_CORE_BUSY_PCT: f64 = 90.0;
+const HOT_CORE_MAX_AGGREGATE_BUSY_PCT: f64 = 50.0;
+
+/// Default steal thresholds, shared by [`StealThresholds::default`] and the CLI.
+pub const DEFAULT_STEAL_DEGRADED_PCT: f64 = 5.0;
+pub const DEFAULT_STEAL_UNHEALTHY_PCT: f64 = 15.0;
+
+/// Steal above these levels means a noisy neighbour or an oversubscribed
+/// hypervisor is taking CPU time from us.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StealThresholds {
+    pub degraded_pct: f64,
+    pub unhealthy_pct: f64,
+}
+
+impl Default for StealThresholds {
+    fn default() -> Self {
+        Self {
+            degraded_pct: DEFAULT_STEAL_DEGRADED_PCT,
+            unhealthy_pct: DEFAULT_STEAL_UNHEALTHY_PCT,
+        }
+    }
+}
+
+/// Tasks in uninterruptible sleep per core. These are almost always waiting
+/// on storage, and a spike shows up here before iowait moves.
+const BLOCKED_PER_CORE_DEGRADED: f64 = 1.0;
+const BLOCKED_PER_CORE_UNHEALTHY: f64 = 4.0;
+
+/// 1-minute load per core. Above 1.0 tasks are queueing for a CPU.
+const LOAD_PER_CORE_DEGRADED: f64 = 1.5;
+const LOAD_PER_CORE_UNHEALTHY: f64 = 3.0;
+
+/// 1m vs 15m load ratio that counts as a trend, and the per-core load below
+/// which the direction is noise.
+const LOAD_TREND_RATIO: f64 = 1.25;
+const LOAD_TREND_NOISE_PER_CORE: f64 = 0.1;
+
+/// USER_HZ assumed when sysconf(_SC_CLK_TCK) cannot be read.
+const DEFAULT_TICKS_PER_SEC: f64 = 100.0;
+
+/// USER_HZ, the unit of the tick counts in /proc/stat and /proc/<pid>/stat.
+/// 100 on mainstream architectures, but the kernel is asked rather than assumed.
+pub(crate) fn ticks_per_sec() -> f64 {
+    static TICKS: OnceLock<f64> = OnceLock::new();
+    *TICKS.get_or_init(|| match sysconf(SysconfVar::CLK_TCK) {
+        Ok(Some(ticks)) if ticks > 0 => ticks as f64,
+        _ => DEFAULT_TICKS_PER_SEC,
+    })
+}
+
+/// An interval whose tick delta is off from the wall clock by more than this
+/// factor (suspend/resume, live migration, a stalled agent) is discarded.
+const MAX_TICK_SKEW: f64 = 2.0;
+
 /// CPU metrics collector that reads directly from /proc/stat.
 pub struct CpuCollector {
-    prev_sample: Option<CpuSample>,
+    proc_root: PathBuf,
+    steal: StealThresholds,
+    prev: Option<(Instant, StatSample)>,
+}
+
+/// Contents of /proc/loadavg.
+#[derive(Debug, Clone, PartialEq)]
+struct LoadAvg {
+    one: f64,
+    five: f64,
+    fifteen: f64,
+    /// currently runnable scheduling entities
+    tasks_running: u64,
+    /// scheduling entities (threads) that exist
+    tasks_total: u64,
+}
+
+/// Everything taken from one read of /proc/stat.
+#[derive(Debug, Clone)]
+struct StatSample {
+    aggregate: CpuSample,
+    cores: Vec<(u32, CpuSample)>,
+    kernel: KernelCounters,
+}
+
+/// Kernel-wide counters and gauges from the non-cpu lines of /proc/stat.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct KernelCounters {
+    /// `ctxt`: context switches since boot.
+    ctxt: u64,
+    /// first column of `intr`: interrupts serviced since boot.
+    intr: u64,
+    /// `processes`: forks since boot.
+    processes: u64,
+    procs_running: u64,
+    procs_blocked: u64,
+    /// `btime`: boot time, seconds since the epoch.
+    btime: u64,
 }
 
 /// Raw CPU tick counts from /proc/stat.
@@ -20,9 +119,60 @@ struct CpuSample {
     irq: u64,
     softirq: u64,
     steal: u64,
+    /// Time running guest vCPUs. The kernel already counts it in `user`
+    /// (and `guest_nice` in `nice`), so it is not part of `total()`.
+    guest: u64,
+    guest_nice: u64,
+}
+
+/// Share of elapsed ticks spent in each state between two samples.
+/// user/system/iowait/idle/steal/guest are disjoint and sum to ~100.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CpuUsage {
+    user_pct: f64,
+    system_pct: f64,
+    iowait_pct: f64,
+    idle_pct: f64,
+    steal_pct: f64,
+    guest_pct: f64,
+}
+
+impl CpuUsage {
+    /// Time this machine spent doing work, excluding time stolen by the hypervisor.
+    fn busy_pct(&self) -> f64 {
+        (100.0 - self.idle_pct - self.steal_pct).max(0.0)
+    }
 }
 
 impl CpuSample {
+    fn fields(&self) -> [u64; 10] {
+        [
+            self.user,
+            self.nice,
+            self.system,
+            self.idle,
+            self.iowait,
+            self.irq,
+            self.softirq,
+            self.steal,
+            self.guest,
+            self.guest_nice,
+        ]
+    }
+
+    /// True if a counter is lower than in `prev`, i.e. the counters were reset.
+    /// idle and iowait are left out: with NO_HZ the kernel estimates them for
+    /// sleeping cores and can step them back slightly on a healthy host.
+    fn regressed_from(&self, prev: &CpuSample) -> bool {
+        const IDLE: usize = 3;
+        const IOWAIT: usize = 4;
+        self.fields()
+            .iter()
+            .zip(prev.fields().iter())
+            .enumerate()
+            .any(|(i, (curr, prev))| i != IDLE && i != IOWAIT && curr < prev)
+    }
+
     fn total(&self) -> u64 {
         self.user
             + self.nice
@@ -36,8 +186,12 @@ impl CpuSample {
 }
 
 impl CpuCollector {
-    pub fn new() -> Self {
-        Self { prev_sample: None }
+    pub fn new(proc_root: impl Into<PathBuf>, steal: StealThresholds) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            steal,
+            prev: None,
+        }
     }
 
     /// Parse the aggregate CPU line from /proc/stat.
@@ -70,13 +224,247 @@ impl CpuCollector {
             irq: parse(6, "irq")?,
             softirq: parse(7, "softirq")?,
             steal: parse(8, "steal")?,
+            // guest columns appeared in 2.6.24 / 2.6.33
+            guest: if parts.len() > 9 {
+                parse(9, "guest")?
+            } else {
+                0
+            },
+            guest_nice: if parts.len() > 10 {
+                parse(10, "guest_nice")?
+            } else {
+                0
+            },
         })
     }
 
-    /// Parse /proc/loadavg for load averages.
-    fn parse_loadavg(content: &str) -> Result<(f64, f64, f64), CollectorError> {
+    /// Parse the aggregate line, per-core lines and kernel counters of /proc/stat.
+    fn parse_stat(stat_content: &str) -> Result<StatSample, CollectorError> {
+        let cpu_line = stat_content
+            .lines()
+            .next()
+            .ok_or_else(|| CollectorError::ParseError {
+                path: "/proc/stat".into(),
+                field: "cpu_line".into(),
+                raw: "empty file".into(),
+            })?;
+
+        Ok(StatSample {
+            aggregate: Self::parse_cpu_line(cpu_line)?,
+            cores: Self::parse_core_lines(stat_content)?,
+            kernel: Self::parse_kernel_counters(stat_content)?,
+        })
+    }
+
+    /// Why the interval between `prev` and `current` cannot be trusted, if it
+    /// can't: cores were hot-added or removed, counters went backwards, or
+    /// the tick delta does not fit the wall-clock window. The caller discards
+    /// such an interval and re-baselines on `current`.
+    fn discard_reason(
+        prev: &StatSample,
+        current: &StatSample,
+        elapsed_secs: f64,
+    ) -> Option<String> {
+        let prev_ids: Vec<u32> = prev.cores.iter().map(|(id, _)| *id).collect();
+        let curr_ids: Vec<u32> = current.cores.iter().map(|(id, _)| *id).collect();
+        if prev_ids != curr_ids {
+            return Some(format!(
+                "online cores changed from {} to {}",
+                prev_ids.len(),
+                curr_ids.len()
+            ));
+        }
+
+        if current.aggregate.regressed_from(&prev.aggregate)
+            || current
+                .cores
+                .iter()
+                .zip(&prev.cores)
+                .any(|((_, curr), (_, prev))| curr.regressed_from(prev))
+        {
+            return Some("cpu counters went backwards".into());
+        }
+
+        let ticks_per_sec = ticks_per_sec();
+        let expected = elapsed_secs * ticks_per_sec * current.cores.len().max(1) as f64;
+        let actual = current
+            .aggregate
+            .total()
+            .saturating_sub(prev.aggregate.total()) as f64;
+        // Too short a window for the tick granularity to say anything.
+        if expected >= ticks_per_sec
+            && (actual > expected * MAX_TICK_SKEW || actual < expected / MAX_TICK_SKEW)
+        {
+            return Some(format!(
+                "tick delta {actual:.0} does not match {elapsed_secs:.1}s window (expected ~{expected:.0})"
+            ));
+        }
+        None
+    }
+
+    /// Parse the per-core `cpuN` lines from /proc/stat, keyed by core id.
+    fn parse_core_lines(stat_content: &str) -> Result<Vec<(u32, CpuSample)>, CollectorError> {
+        stat_content
+            .lines()
+            .filter_map(|line| {
+                let id = line.split_whitespace().next()?.strip_prefix("cpu")?;
+                let id = id.parse::<u32>().ok()?;
+                Some(Self::parse_cpu_line(line).map(|sample| (id, sample)))
+            })
+            .collect()
+    }
+
+    /// Percentages of time spent in each state between two samples. Guest
+    /// time is split out of user/nice so every tick is counted once.
+    fn usage(prev: &CpuSample, current: &CpuSample) -> CpuUsage {
+        let total_delta = current.total().saturating_sub(prev.total());
+        if total_delta == 0 {
+            return CpuUsage {
+                idle_pct: 100.0,
+                ..CpuUsage::default()
+            };
+        }
+        let td = total_delta as f64;
+        let pct = |ticks: u64| ticks as f64 / td * 100.0;
+
+        let guest = current.guest.saturating_sub(prev.guest)
+            + current.guest_nice.saturating_sub(prev.guest_nice);
+        let user = (current.user.saturating_sub(prev.user)
+            + current.nice.saturating_sub(prev.nice))
+        .saturating_sub(guest);
+
+        CpuUsage {
+            user_pct: pct(user),
+            system_pct: pct(current.system.saturating_sub(prev.system)
+                + current.irq.saturating_sub(prev.irq)
+                + current.softirq.saturating_sub(prev.softirq)),
+            iowait_pct: pct(current.iowait.saturating_sub(prev.iowait)),
+            idle_pct: pct(current.idle.saturating_sub(prev.idle)),
+            steal_pct: pct(current.steal.saturating_sub(prev.steal)),
+            guest_pct: pct(guest),
+        }
+    }
+
+    /// Per-core breakdown for cores present in both samples.
+    fn per_core_usage(
+        prev: &[(u32, CpuSample)],
+        current: &[(u32, CpuSample)],
+    ) -> Vec<CoreSnapshot> {
+        current
+            .iter()
+            .filter_map(|(core, curr)| {
+                let (_, prev) = prev.iter().find(|(id, _)| id == core)?;
+                let usage = Self::usage(prev, curr);
+                Some(CoreSnapshot {
+                    core: *core,
+                    user_pct: usage.user_pct,
+                    system_pct: usage.system_pct,
+                    iowait_pct: usage.iowait_pct,
+                    idle_pct: usage.idle_pct,
+                    steal_pct: usage.steal_pct,
+                })
+            })
+            .collect()
+    }
+
+    /// Cores saturated while the machine as a whole looks mostly idle.
+    fn hot_cores(aggregate_busy_pct: f64, per_core: &[CoreSnapshot]) -> Vec<u32> {
+        if aggregate_busy_pct >= HOT_CORE_MAX_AGGREGATE_BUSY_PCT {
+            return Vec::new();
+        }
+        per_core
+            .iter()
+            .filter(|c| 100.0 - c.idle_pct - c.steal_pct >= HOT_CORE_BUSY_PCT)
+            .map(|c| c.core)
+            .collect()
+    }
+
+    /// Parse the ctxt/intr/processes/procs_*/btime lines of /proc/stat.
+    fn parse_kernel_counters(stat_content: &str) -> Result<KernelCounters, CollectorError> {
+        let mut counters = KernelCounters::default();
+        for line in stat_content.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(raw)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let slot = match key {
+                "ctxt" => &mut counters.ctxt,
+                "intr" => &mut counters.intr,
+                "processes" => &mut counters.processes,
+                "procs_running" => &mut counters.procs_running,
+                "procs_blocked" => &mut counters.procs_blocked,
+                "btime" => &mut counters.btime,
+                _ => continue,
+            };
+            *slot = raw.parse().map_err(|_| CollectorError::ParseError {
+                path: "/proc/stat".into(),
+                field: key.into(),
+                raw: raw.to_string(),
+            })?;
+        }
+        Ok(counters)
+    }
+
+    /// Per-second rates of the cumulative counters over `elapsed_secs`;
+    /// gauges are passed through as-is.
+    fn kernel_activity(
+        prev: Option<&KernelCounters>,
+        current: &KernelCounters,
+        elapsed_secs: f64,
+    ) -> KernelActivitySnapshot {
+        let rate = |f: fn(&KernelCounters) -> u64| {
+            let prev = prev.filter(|_| elapsed_secs > 0.0)?;
+            f(current)
+                .checked_sub(f(prev))
+                .map(|delta| delta as f64 / elapsed_secs)
+        };
+        KernelActivitySnapshot {
+            context_switches_per_sec: rate(|c| c.ctxt),
+            interrupts_per_sec: rate(|c| c.intr),
+            forks_per_sec: rate(|c| c.processes),
+            procs_running: current.procs_running,
+            procs_blocked: current.procs_blocked,
+            boot_time: current.btime,
+        }
+    }
+
+    /// Map the snapshot to a health status. A discarded interval carries no
+    /// utilisation data, so only the gauges (blocked tasks, load) are evaluated.
+    fn evaluate(snapshot: &CpuSnapshot, steal: &StealThresholds) -> CheckStatus {
+        let blocked_per_core =
+            snapshot.activity.procs_blocked as f64 / snapshot.num_cores.max(1) as f64;
+        let load_per_core = snapshot.load_per_core_1m;
+
+        let mut unhealthy = blocked_per_core > BLOCKED_PER_CORE_UNHEALTHY
+            || load_per_core > LOAD_PER_CORE_UNHEALTHY;
+        let mut degraded =
+            blocked_per_core > BLOCKED_PER_CORE_DEGRADED || load_per_core > LOAD_PER_CORE_DEGRADED;
+
+        if snapshot.discarded_reason.is_none() {
+            let busy_pct = (100.0 - snapshot.idle_pct - snapshot.steal_pct).max(0.0);
+            unhealthy |= snapshot.iowait_pct > 30.0
+                || busy_pct > 95.0
+                || snapshot.steal_pct > steal.unhealthy_pct;
+            degraded |= snapshot.iowait_pct > 10.0
+                || busy_pct > 80.0
+                || snapshot.steal_pct > steal.degraded_pct
+                || !snapshot.hot_cores.is_empty();
+        }
+
+        if unhealthy {
+            CheckStatus::Unhealthy
+        } else if degraded {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+
+    /// Parse /proc/loadavg: the three load averages and the
+    /// `running/total` scheduling entities field.
+    fn parse_loadavg(content: &str) -> Result<LoadAvg, CollectorError> {
         let parts: Vec<&str> = content.split_whitespace().collect();
-        if parts.len() < 3 {
+        if parts.len() < 4 {
             return Err(CollectorError::ParseError {
                 path: "/proc/loadavg".into(),
                 field: "loadavg".into(),
@@ -94,7 +482,35 @@ impl CpuCollector {
                 })
         };
 
-        Ok((parse(0, "1m")?, parse(1, "5m")?, parse(2, "15m")?))
+        let tasks_err = || CollectorError::ParseError {
+            path: "/proc/loadavg".into(),
+            field: "tasks".into(),
+            raw: parts[3].to_string(),
+        };
+        let (running, total) = parts[3].split_once('/').ok_or_else(tasks_err)?;
+
+        Ok(LoadAvg {
+            one: parse(0, "1m")?,
+            five: parse(1, "5m")?,
+            fifteen: parse(2, "15m")?,
+            tasks_running: running.parse().map_err(|_| tasks_err())?,
+            tasks_total: total.parse().map_err(|_| tasks_err())?,
+        })
+    }
+
+    /// Direction of the load from the 1m vs 15m averages. Loads too small to
+    /// matter on this many cores are always steady.
+    fn load_trend(load: &LoadAvg, num_cores: u32) -> LoadTrend {
+        let noise_floor = LOAD_TREND_NOISE_PER_CORE * num_cores.max(1) as f64;
+        if load.one.max(load.fifteen) < noise_floor {
+            LoadTrend::Steady
+        } else if load.one > load.fifteen * LOAD_TREND_RATIO {
+            LoadTrend::Rising
+        } else if load.one * LOAD_TREND_RATIO < load.fifteen {
+            LoadTrend::Falling
+        } else {
+            LoadTrend::Steady
+        }
     }
 
     /// Count CPU cores from /proc/stat (lines starting with "cpu" followed by a digit).
@@ -102,7 +518,7 @@ impl CpuCollector {
         stat_content
             .lines()
             .filter(|line| {
-                line.starts_with("cpu") && line.chars().nth(3).map_or(false, |c| c.is_ascii_digit())
+                line.starts_with("cpu") && line.chars().nth(3).is_some_and(|c| c.is_ascii_digit())
             })
             .count() as u32
     }
@@ -116,89 +532,115 @@ impl Collector for CpuCollector {
 
     async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
         // Read /proc/stat for CPU ticks
+        let stat_path = self.proc_root.join("stat");
         let stat_content =
-            fs::read_to_string("/proc/stat")
+            fs::read_to_string(&stat_path)
                 .await
                 .map_err(|e| CollectorError::ProcReadError {
-                    path: "/proc/stat".into(),
+                    path: stat_path.display().to_string(),
                     source: e,
                 })?;
 
-        let cpu_line = stat_content
-            .lines()
-            .next()
-            .ok_or_else(|| CollectorError::ParseError {
-                path: "/proc/stat".into(),
-                field: "cpu_line".into(),
-                raw: "empty file".into(),
-            })?;
-
-        let current = Self::parse_cpu_line(cpu_line)?;
+        let current = Self::parse_stat(&stat_content)?;
         let num_cores = Self::count_cores(&stat_content);
+        let now = Instant::now();
 
         // Read load averages
-        let loadavg_content = fs::read_to_string("/proc/loadavg").await.map_err(|e| {
-            CollectorError::ProcReadError {
-                path: "/proc/loadavg".into(),
-                source: e,
-            }
-        })?;
-        let (load_1m, load_5m, load_15m) = Self::parse_loadavg(&loadavg_content)?;
+        let loadavg_path = self.proc_root.join("loadavg");
+        let loadavg_content =
+            fs::read_to_string(&loadavg_path)
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: loadavg_path.display().to_string(),
+                    source: e,
+                })?;
+        let load = Self::parse_loadavg(&loadavg_content)?;
+        let cores_f = num_cores.max(1) as f64;
 
-        // Compute deltas if we have a previous sample
-        let (user_pct, system_pct, iowait_pct, idle_pct) = if let Some(ref prev) = self.prev_sample
-        {
-            let total_delta = current.total().saturating_sub(prev.total());
-            if total_delta == 0 {
-                (0.0, 0.0, 0.0, 100.0)
-            } else {
-                let td = total_delta as f64;
-                (
-                    (current.user.saturating_sub(prev.user)
-                        + current.nice.saturating_sub(prev.nice)) as f64
-                        / td
-                        * 100.0,
-                    (current.system.saturating_sub(prev.system)
-                        + current.irq.saturating_sub(prev.irq)
-                        + current.softirq.saturating_sub(prev.softirq)) as f64
-                        / td
-                        * 100.0,
-                    current.iowait.saturating_sub(prev.iowait) as f64 / td * 100.0,
-                    current.idle.saturating_sub(prev.idle) as f64 / td * 100.0,
-                )
+        // Compute deltas if we have a trustworthy previous sample
+        let (elapsed_secs, discarded) = match &self.prev {
+            Some((at, prev)) => {
+                let elapsed = (now - *at).as_secs_f64();
+                (elapsed, Self::discard_reason(prev, &current, elapsed))
             }
-        } else {
             // First sample — can't compute delta yet.
-            // Return zeros; next collection will have real data.
-            (0.0, 0.0, 0.0, 0.0)
+            None => (0.0, Some("baseline sample".to_string())),
         };
+        let prev = self
+            .prev
+            .as_ref()
+            .map(|(_, p)| p)
+            .filter(|_| discarded.is_none());
 
-        self.prev_sample = Some(current);
+        // Return zeros for a discarded interval; next collection will have real data.
+        let usage = prev
+            .map(|p| Self::usage(&p.aggregate, &current.aggregate))
+            .unwrap_or_default();
+        let per_core = prev
+            .map(|p| Self::per_core_usage(&p.cores, &current.cores))
+            .unwrap_or_default();
+        let hot_cores = Self::hot_cores(usage.busy_pct(), &per_core);
+        let activity =
+            Self::kernel_activity(prev.map(|p| &p.kernel), &current.kernel, elapsed_secs);
+
+        if let Some(reason) = &discarded {
+            tracing::debug!(reason = %reason, "discarding cpu interval and re-baselining");
+        }
+        self.prev = Some((now, current));
 
         let snapshot = CpuSnapshot {
-            user_pct,
-            system_pct,
-            iowait_pct,
-            idle_pct,
+            user_pct: usage.user_pct,
+            system_pct: usage.system_pct,
+            iowait_pct: usage.iowait_pct,
+            idle_pct: usage.idle_pct,
+            steal_pct: usage.steal_pct,
+            guest_pct: usage.guest_pct,
             num_cores,
-            load_avg_1m: load_1m,
-            load_avg_5m: load_5m,
-            load_avg_15m: load_15m,
+            load_avg_1m: load.one,
+            load_avg_5m: load.five,
+            load_avg_15m: load.fifteen,
+            load_per_core_1m: load.one / cores_f,
+            load_per_core_5m: load.five / cores_f,
+            load_per_core_15m: load.fifteen / cores_f,
+            load_trend: Self::load_trend(&load, num_cores),
+            tasks_running: load.tasks_running,
+            tasks_total: load.tasks_total,
+            per_core,
+            hot_cores,
+            activity,
+            sample_window_ms: (elapsed_secs * 1000.0).round() as u64,
+            discarded_reason: discarded,
         };
 
         // Determine health status
-        let status = if iowait_pct > 30.0 || (100.0 - idle_pct) > 95.0 {
-            CheckStatus::Unhealthy
-        } else if iowait_pct > 10.0 || (100.0 - idle_pct) > 80.0 {
-            CheckStatus::Degraded
-        } else {
-            CheckStatus::Healthy
-        };
+        let status = Self::evaluate(&snapshot, &self.steal);
 
-        let message = format!(
-            "user={:.1}% sys={:.1}% iowait={:.1}% idle={:.1}% load={:.2}",
-            user_pct, system_pct, iowait_pct, idle_pct, load_1m,
+        let mut message = format!(
+            "user={:.1}% sys={:.1}% iowait={:.1}% idle={:.1}% steal={:.1}% guest={:.1}% load={:.2}",
+            usage.user_pct,
+            usage.system_pct,
+            usage.iowait_pct,
+            usage.idle_pct,
+            usage.steal_pct,
+            usage.guest_pct,
+            load.one,
         );
+        message.push_str(&format!(
+            " load/core={:.2} trend={:?} running={} blocked={}",
+            snapshot.load_per_core_1m,
+            snapshot.load_trend,
+            snapshot.activity.procs_running,
+            snapshot.activity.procs_blocked
+        ));
+        if let Some(ctxt) = snapshot.activity.context_switches_per_sec {
+            message.push_str(&format!(" ctxt/s={:.0}", ctxt));
+        }
+        if !snapshot.hot_cores.is_empty() {
+            message.push_str(&format!(" hot_cores={:?}", snapshot.hot_cores));
+        }
+        if let Some(reason) = &snapshot.discarded_reason {
+            message = format!("interval discarded ({reason}), re-baselined");
+        }
 
         Ok(CollectionResult {
             check_name: self.name().to_string(),
@@ -206,7 +648,8 @@ impl Collector for CpuCollector {
             message,
             metadata: HashMap::new(),
             latency_us: 0, // filled by timed_collect wrapper
-            payload: MetricPayload::Cpu(snapshot),
+            in_maintenance: false,
+            payload: MetricPayload::Cpu(Box::new(snapshot)),
         })
     }
 }
@@ -247,13 +690,345 @@ cpu1 1335498 35507 523368 13200746 4990 0 3670 0 0 0";
         assert_eq!(CpuCollector::count_cores(SAMPLE_STAT), 2);
     }
 
+    #[test]
+    fn test_parse_core_lines() {
+        let cores = CpuCollector::parse_core_lines(SAMPLE_STAT).unwrap();
+        assert_eq!(cores.len(), 2);
+        assert_eq!(cores[0].0, 0);
+        assert_eq!(cores[1].0, 1);
+        assert_eq!(cores[1].1.user, 1335498);
+    }
+
+    #[test]
+    fn test_hot_core_detected_while_aggregate_idle() {
+        let core = |core: u32, idle_pct: f64| CoreSnapshot {
+            core,
+            user_pct: 100.0 - idle_pct,
+            system_pct: 0.0,
+            iowait_pct: 0.0,
+            idle_pct,
+            steal_pct: 0.0,
+        };
+        let mut per_core: Vec<CoreSnapshot> = (0..8).map(|c| core(c, 98.0)).collect();
+        per_core[3] = core(3, 2.0);
+        // 8 cores, one pegged: aggregate is ~14% busy
+        assert_eq!(CpuCollector::hot_cores(14.0, &per_core), vec![3]);
+        // whole machine busy: not a single-core bottleneck
+        assert!(CpuCollector::hot_cores(70.0, &per_core).is_empty());
+    }
+
+    #[test]
+    fn test_per_core_usage_matches_core_ids() {
+        let sample = |user: u64, idle: u64| CpuSample {
+            user,
+            nice: 0,
+            system: 0,
+            idle,
+            iowait: 0,
+            irq: 0,
+            softirq: 0,
+            steal: 0,
+            guest: 0,
+            guest_nice: 0,
+        };
+        let prev = vec![(0, sample(100, 100)), (1, sample(100, 100))];
+        let curr = vec![(0, sample(200, 100)), (1, sample(100, 200))];
+        let per_core = CpuCollector::per_core_usage(&prev, &curr);
+        assert!((per_core[0].user_pct - 100.0).abs() < f64::EPSILON);
+        assert!((per_core[1].idle_pct - 100.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_parse_guest_columns() {
+        let sample = CpuCollector::parse_cpu_line("cpu  100 10 50 800 5 0 2 30 40 4").unwrap();
+        assert_eq!(sample.steal, 30);
+        assert_eq!(sample.guest, 40);
+        assert_eq!(sample.guest_nice, 4);
+        // guest is already inside user/nice
+        assert_eq!(sample.total(), 100 + 10 + 50 + 800 + 5 + 2 + 30);
+
+        let old_kernel = CpuCollector::parse_cpu_line("cpu  100 10 50 800 5 0 2 30").unwrap();
+        assert_eq!(old_kernel.guest, 0);
+    }
+
+    #[test]
+    fn test_usage_separates_steal_and_guest() {
+        let prev = CpuCollector::parse_cpu_line("cpu  0 0 0 0 0 0 0 0 0 0").unwrap();
+        // 60 user ticks of which 20 were guest, 10 steal, 30 idle
+        let curr = CpuCollector::parse_cpu_line("cpu  60 0 0 30 0 0 0 10 20 0").unwrap();
+        let usage = CpuCollector::usage(&prev, &curr);
+        assert!((usage.user_pct - 40.0).abs() < 1e-9);
+        assert!((usage.guest_pct - 20.0).abs() < 1e-9);
+        assert!((usage.steal_pct - 10.0).abs() < 1e-9);
+        assert!((usage.idle_pct - 30.0).abs() < 1e-9);
+        let sum = usage.user_pct
+            + usage.system_pct
+            + usage.iowait_pct
+            + usage.idle_pct
+            + usage.steal_pct
+            + usage.guest_pct;
+        assert!((sum - 100.0).abs() < 1e-9);
+        assert!((usage.busy_pct() - 60.0).abs() < 1e-9);
+    }
+
+    const SAMPLE_STAT_COUNTERS: &str = "\
+cpu  10132153 290696 3084719 46828483 16683 0 25195 0 0 0
+cpu0 1393280 32966 572056 13343292 6130 0 17875 0 0 0
+intr 199292724 22 0 0 0 0 0 0 0 1 0 0 0 0
+ctxt 350009932
+btime 1760781600
+processes 812345
+procs_running 3
+procs_blocked 7
+softirq 95468238 3 14125307 101 2064458 0 0 10 0 0 79278359";
+
+    #[test]
+    fn test_parse_kernel_counters() {
+        let k = CpuCollector::parse_kernel_counters(SAMPLE_STAT_COUNTERS).unwrap();
+        assert_eq!(k.ctxt, 350009932);
+        assert_eq!(k.intr, 199292724);
+        assert_eq!(k.processes, 812345);
+        assert_eq!(k.procs_running, 3);
+        assert_eq!(k.procs_blocked, 7);
+        assert_eq!(k.btime, 1760781600);
+    }
+
+    #[test]
+    fn test_kernel_activity_rates() {
+        let prev = CpuCollector::parse_kernel_counters(SAMPLE_STAT_COUNTERS).unwrap();
+        let curr = KernelCounters {
+            ctxt: prev.ctxt + 10_000,
+            intr: prev.intr + 4_000,
+            processes: prev.processes + 50,
+            ..prev.clone()
+        };
+        let a = CpuCollector::kernel_activity(Some(&prev), &curr, 2.0);
+        assert_eq!(a.context_switches_per_sec, Some(5_000.0));
+        assert_eq!(a.interrupts_per_sec, Some(2_000.0));
+        assert_eq!(a.forks_per_sec, Some(25.0));
+        assert_eq!(a.procs_blocked, 7);
+
+        let first = CpuCollector::kernel_activity(None, &curr, 0.0);
+        assert_eq!(first.context_switches_per_sec, None);
+        assert_eq!(first.boot_time, 1760781600);
+    }
+
+    fn idle_snapshot() -> CpuSnapshot {
+        CpuSnapshot {
+            user_pct: 5.0,
+            system_pct: 2.0,
+            iowait_pct: 1.0,
+            idle_pct: 92.0,
+            steal_pct: 0.0,
+            guest_pct: 0.0,
+            num_cores: 4,
+            load_avg_1m: 0.5,
+            load_avg_5m: 0.5,
+            load_avg_15m: 0.5,
+            load_per_core_1m: 0.125,
+            load_per_core_5m: 0.125,
+            load_per_core_15m: 0.125,
+            load_trend: LoadTrend::Steady,
+            tasks_running: 1,
+            tasks_total: 300,
+            per_core: Vec::new(),
+            hot_cores: Vec::new(),
+            activity: KernelActivitySnapshot {
+                context_switches_per_sec: None,
+                interrupts_per_sec: None,
+                forks_per_sec: None,
+                procs_running: 1,
+                procs_blocked: 0,
+                boot_time: 0,
+            },
+            sample_window_ms: 5000,
+            discarded_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_blocked_tasks_raise_status() {
+        let mut snapshot = idle_snapshot();
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Healthy
+        );
+        snapshot.activity.procs_blocked = 6;
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Degraded
+        );
+        snapshot.activity.procs_blocked = 20;
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Unhealthy
+        );
+    }
+
+    fn stat_sample(cores: &[&str]) -> StatSample {
+        let mut totals = [0u64; 8];
+        let mut lines = String::new();
+        for (i, core) in cores.iter().enumerate() {
+            lines.push_str(&format!("cpu{i} {core}\n"));
+            for (t, v) in totals.iter_mut().zip(core.split_whitespace()) {
+                *t += v.parse::<u64>().unwrap();
+            }
+        }
+        let aggregate: Vec<String> = totals.iter().map(|t| t.to_string()).collect();
+        let content = format!("cpu  {}\n{lines}", aggregate.join(" "));
+        CpuCollector::parse_stat(&content).unwrap()
+    }
+
+    #[test]
+    fn test_discard_on_core_count_change() {
+        let prev = stat_sample(&["100 0 0 100 0 0 0 0", "100 0 0 100 0 0 0 0"]);
+        let curr = stat_sample(&["200 0 0 200 0 0 0 0"]);
+        let reason = CpuCollector::discard_reason(&prev, &curr, 1.0).unwrap();
+        assert!(reason.contains("from 2 to 1"), "{reason}");
+    }
+
+    #[test]
+    fn test_discard_on_counter_regression() {
+        let prev = stat_sample(&["500 0 0 500 0 0 0 0"]);
+        let curr = stat_sample(&["10 0 0 1100 0 0 0 0"]);
+        assert_eq!(
+            CpuCollector::discard_reason(&prev, &curr, 1.0).as_deref(),
+            Some("cpu counters went backwards")
+        );
+    }
+
+    #[test]
+    fn test_idle_and_iowait_steps_back_are_not_a_reset() {
+        let prev = stat_sample(&["500 0 0 500 40 0 0 0"]);
+        let curr = stat_sample(&["550 0 0 550 38 0 0 0"]);
+        assert_eq!(CpuCollector::discard_reason(&prev, &curr, 1.0), None);
+        let curr = stat_sample(&["550 0 0 498 45 0 0 0"]);
+        assert_eq!(CpuCollector::discard_reason(&prev, &curr, 1.0), None);
+    }
+
+    #[test]
+    fn test_discard_on_tick_skew() {
+        let prev = stat_sample(&["0 0 0 0 0 0 0 0", "0 0 0 0 0 0 0 0"]);
+        // 2 cores, 5s window: ~1000 ticks expected
+        let ok = stat_sample(&["250 0 0 250 0 0 0 0", "100 0 0 400 0 0 0 0"]);
+        assert_eq!(CpuCollector::discard_reason(&prev, &ok, 5.0), None);
+        // a suspended VM resumes with a tiny delta after a long wall window
+        assert!(CpuCollector::discard_reason(&prev, &ok, 60.0).is_some());
+        // live migration catch-up: far more ticks than the window allows
+        assert!(CpuCollector::discard_reason(&prev, &ok, 1.0).is_some());
+        // sub-second windows are too coarse to judge
+        assert_eq!(CpuCollector::discard_reason(&prev, &ok, 0.2), None);
+    }
+
+    #[tokio::test]
+    async fn test_baseline_interval_is_not_unhealthy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("stat"), SAMPLE_STAT).unwrap();
+        std::fs::write(dir.path().join("loadavg"), "0.50 0.75 1.00 2/1234 5678\n").unwrap();
+        let mut collector = CpuCollector::new(dir.path(), StealThresholds::default());
+        let first = collector.collect().await.unwrap();
+        assert_ne!(first.status, CheckStatus::Unhealthy);
+        match first.payload {
+            MetricPayload::Cpu(cpu) => {
+                assert_eq!(cpu.discarded_reason.as_deref(), Some("baseline sample"));
+                assert_eq!(cpu.sample_window_ms, 0);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
     #[test]
     fn test_parse_loadavg() {
         let content = "0.50 0.75 1.00 2/1234 5678";
-        let (l1, l5, l15) = CpuCollector::parse_loadavg(content).unwrap();
-        assert!((l1 - 0.50).abs() < f64::EPSILON);
-        assert!((l5 - 0.75).abs() < f64::EPSILON);
-        assert!((l15 - 1.00).abs() < f64::EPSILON);
+        let load = CpuCollector::parse_loadavg(content).unwrap();
+        assert!((load.one - 0.50).abs() < f64::EPSILON);
+        assert!((load.five - 0.75).abs() < f64::EPSILON);
+        assert!((load.fifteen - 1.00).abs() < f64::EPSILON);
+        assert_eq!(load.tasks_running, 2);
+        assert_eq!(load.tasks_total, 1234);
+    }
+
+    #[test]
+    fn test_parse_loadavg_bad_tasks_field() {
+        assert!(CpuCollector::parse_loadavg("0.50 0.75 1.00 21234 5678").is_err());
+        assert!(CpuCollector::parse_loadavg("0.50 0.75 1.00").is_err());
+    }
+
+    #[test]
+    fn test_load_trend() {
+        let load = |one: f64, fifteen: f64| LoadAvg {
+            one,
+            five: (one + fifteen) / 2.0,
+            fifteen,
+            tasks_running: 1,
+            tasks_total: 100,
+        };
+        assert_eq!(
+            CpuCollector::load_trend(&load(8.0, 2.0), 4),
+            LoadTrend::Rising
+        );
+        assert_eq!(
+            CpuCollector::load_trend(&load(1.0, 6.0), 4),
+            LoadTrend::Falling
+        );
+        assert_eq!(
+            CpuCollector::load_trend(&load(4.1, 4.0), 4),
+            LoadTrend::Steady
+        );
+        // tiny loads on a big box are noise
+        assert_eq!(
+            CpuCollector::load_trend(&load(0.3, 0.01), 16),
+            LoadTrend::Steady
+        );
+    }
+
+    #[test]
+    fn test_steal_thresholds() {
+        let mut snapshot = idle_snapshot();
+        snapshot.steal_pct = 8.0;
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Degraded
+        );
+        // an oversubscribed fleet can tolerate more steal
+        let lenient = StealThresholds {
+            degraded_pct: 10.0,
+            unhealthy_pct: 30.0,
+        };
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &lenient),
+            CheckStatus::Healthy
+        );
+        snapshot.steal_pct = 20.0;
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Unhealthy
+        );
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &lenient),
+            CheckStatus::Degraded
+        );
+    }
+
+    #[test]
+    fn test_load_per_core_raises_status() {
+        let mut snapshot = idle_snapshot();
+        snapshot.load_per_core_1m = 2.0;
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Degraded
+        );
+        snapshot.load_per_core_1m = 3.5;
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Unhealthy
+        );
+        // load is a gauge, it still counts when the interval was discarded
+        snapshot.discarded_reason = Some("baseline sample".into());
+        assert_eq!(
+            CpuCollector::evaluate(&snapshot, &StealThresholds::default()),
+            CheckStatus::Unhealthy
+        );
     }
 
     #[test]
@@ -267,6 +1042,8 @@ cpu1 1335498 35507 523368 13200746 4990 0 3670 0 0 0";
             irq: 0,
             softirq: 0,
             steal: 0,
+            guest: 0,
+            guest_nice: 0,
         };
         let curr = CpuSample {
             user: 1200,
@@ -277,6 +1054,8 @@ cpu1 1335498 35507 523368 13200746 4990 0 3670 0 0 0";
             irq: 0,
             softirq: 0,
             steal: 0,
+            guest: 0,
+            guest_nice: 0,
         };
         let total_delta = curr.total() - prev.total();
         assert_eq!(total_delta, 400);
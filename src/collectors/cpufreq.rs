@@ -0,0 +1,389 @@
+use super::{CheckStatus, CollectionResult, Collector, CoreFreq, CpuFreqSnapshot, MetricPayload};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Governors that trade latency for power. Fine for a laptop, not for a
+/// database host.
+const POWER_SAVING_GOVERNORS: &[&str] = &["powersave", "conservative"];
+
+/// Drivers that pick frequencies in hardware (HWP/CPPC). Under them
+/// "powersave" is the normal default policy and still boosts on load,
+/// unlike the generic governor of the same name that pins the minimum.
+const HARDWARE_MANAGED_DRIVERS: &[&str] = &["intel_pstate", "amd-pstate-epp"];
+
+/// CPU frequency and thermal throttling collector reading
+/// /sys/devices/system/cpu/cpu*/{cpufreq,thermal_throttle}.
+pub struct CpuFreqCollector {
+    sys_root: PathBuf,
+    prev: Option<Vec<CpuFreqSample>>,
+}
+
+/// Raw per-CPU values read from sysfs. Every file is optional: VMs often
+/// expose no cpufreq at all and thermal_throttle is Intel-only.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CpuFreqSample {
+    core: u32,
+    package: Option<u32>,
+    cur_khz: Option<u64>,
+    min_khz: Option<u64>,
+    max_khz: Option<u64>,
+    governor: Option<String>,
+    driver: Option<String>,
+    core_throttle_count: Option<u64>,
+    package_throttle_count: Option<u64>,
+}
+
+impl CpuFreqCollector {
+    pub fn new(sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            sys_root: sys_root.into(),
+            prev: None,
+        }
+    }
+
+    fn read_u64(path: &Path) -> Option<u64> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    fn read_string(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+    }
+
+    /// Whether a core's policy trades latency for power.
+    fn is_power_saving(governor: Option<&str>, driver: Option<&str>) -> bool {
+        match governor {
+            Some("powersave") => !driver.is_some_and(|d| HARDWARE_MANAGED_DRIVERS.contains(&d)),
+            Some(g) => POWER_SAVING_GOVERNORS.contains(&g),
+            None => false,
+        }
+    }
+
+    /// Read every cpuN directory under `<sys_root>/devices/system/cpu`.
+    fn read_samples(sys_root: &Path) -> Result<Vec<CpuFreqSample>, CollectorError> {
+        let cpu_dir = sys_root.join("devices/system/cpu");
+        let entries = fs::read_dir(&cpu_dir).map_err(|e| CollectorError::ProcReadError {
+            path: cpu_dir.display().to_string(),
+            source: e,
+        })?;
+
+        let mut samples: Vec<CpuFreqSample> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let core = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("cpu")?
+                    .parse::<u32>()
+                    .ok()?;
+                let dir = entry.path();
+                let freq = dir.join("cpufreq");
+                let throttle = dir.join("thermal_throttle");
+                Some(CpuFreqSample {
+                    core,
+                    package: Self::read_u64(&dir.join("topology/physical_package_id"))
+                        .map(|p| p as u32),
+                    cur_khz: Self::read_u64(&freq.join("scaling_cur_freq")),
+                    min_khz: Self::read_u64(&freq.join("scaling_min_freq")),
+                    max_khz: Self::read_u64(&freq.join("scaling_max_freq"))
+                        .or_else(|| Self::read_u64(&freq.join("cpuinfo_max_freq"))),
+                    governor: Self::read_string(&freq.join("scaling_governor")),
+                    driver: Self::read_string(&freq.join("scaling_driver")),
+                    core_throttle_count: Self::read_u64(&throttle.join("core_throttle_count")),
+                    package_throttle_count: Self::read_u64(
+                        &throttle.join("package_throttle_count"),
+                    ),
+                })
+            })
+            .collect();
+        samples.sort_by_key(|s| s.core);
+        Ok(samples)
+    }
+
+    /// Throttle events since the previous sample. Core counters are summed;
+    /// the package counter is exposed on every CPU of the package, so only
+    /// the largest delta per package counts.
+    fn throttle_events(prev: &[CpuFreqSample], current: &[CpuFreqSample]) -> u64 {
+        let delta = |curr: Option<u64>, prev: Option<u64>| match (curr, prev) {
+            // a counter lower than before means the CPU went offline and back
+            (Some(c), Some(p)) => c.saturating_sub(p),
+            _ => 0,
+        };
+
+        let mut core_events = 0;
+        let mut package_events: HashMap<Option<u32>, u64> = HashMap::new();
+        for curr in current {
+            let Some(p) = prev.iter().find(|p| p.core == curr.core) else {
+                continue;
+            };
+            core_events += delta(curr.core_throttle_count, p.core_throttle_count);
+            let pkg = delta(curr.package_throttle_count, p.package_throttle_count);
+            let slot = package_events.entry(curr.package).or_default();
+            *slot = (*slot).max(pkg);
+        }
+        core_events + package_events.values().sum::<u64>()
+    }
+
+    fn evaluate(snapshot: &CpuFreqSnapshot) -> CheckStatus {
+        if snapshot.throttle_events > 0 || !snapshot.power_saving_cores.is_empty() {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for CpuFreqCollector {
+    fn name(&self) -> &'static str {
+        "cpufreq"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let sys_root = self.sys_root.clone();
+        let samples = tokio::task::spawn_blocking(move || Self::read_samples(&sys_root))
+            .await
+            .map_err(|e| CollectorError::ProcReadError {
+                path: self.sys_root.display().to_string(),
+                source: std::io::Error::other(e),
+            })??;
+
+        let throttle_events = self
+            .prev
+            .as_ref()
+            .map(|prev| Self::throttle_events(prev, &samples))
+            .unwrap_or(0);
+
+        let cores: Vec<CoreFreq> = samples
+            .iter()
+            .map(|s| CoreFreq {
+                core: s.core,
+                cur_khz: s.cur_khz,
+                min_khz: s.min_khz,
+                max_khz: s.max_khz,
+                governor: s.governor.clone(),
+                driver: s.driver.clone(),
+                core_throttle_count: s.core_throttle_count,
+                package_throttle_count: s.package_throttle_count,
+            })
+            .collect();
+        let power_saving_cores: Vec<u32> = cores
+            .iter()
+            .filter(|c| Self::is_power_saving(c.governor.as_deref(), c.driver.as_deref()))
+            .map(|c| c.core)
+            .collect();
+        let cpufreq_available = cores.iter().any(|c| c.cur_khz.is_some());
+
+        self.prev = Some(samples);
+
+        let snapshot = CpuFreqSnapshot {
+            cpufreq_available,
+            cores,
+            throttle_events,
+            power_saving_cores,
+        };
+        let status = Self::evaluate(&snapshot);
+
+        let message = if !snapshot.cpufreq_available {
+            format!(
+                "cpufreq not exposed on {} cpus, throttle_events={}",
+                snapshot.cores.len(),
+                throttle_events
+            )
+        } else {
+            let (sum, n) = snapshot
+                .cores
+                .iter()
+                .filter_map(|c| c.cur_khz)
+                .fold((0u64, 0u64), |(sum, n), khz| (sum + khz, n + 1));
+            let governors: Vec<&str> = {
+                let mut g: Vec<&str> = snapshot
+                    .cores
+                    .iter()
+                    .filter_map(|c| c.governor.as_deref())
+                    .collect();
+                g.sort_unstable();
+                g.dedup();
+                g
+            };
+            format!(
+                "avg_freq={} MHz governor={} throttle_events={} power_saving_cores={}",
+                sum / n.max(1) / 1000,
+                governors.join(","),
+                throttle_events,
+                snapshot.power_saving_cores.len()
+            )
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::CpuFreq(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_driver(sys_root: &Path, core: u32, driver: &str) {
+        fs::write(
+            sys_root.join(format!(
+                "devices/system/cpu/cpu{core}/cpufreq/scaling_driver"
+            )),
+            format!("{driver}\n"),
+        )
+        .unwrap();
+    }
+
+    fn write_cpu(sys_root: &Path, core: u32, package: u32, cur_khz: u64, governor: &str) {
+        let cpu = sys_root.join(format!("devices/system/cpu/cpu{core}"));
+        let freq = cpu.join("cpufreq");
+        fs::create_dir_all(&freq).unwrap();
+        fs::create_dir_all(cpu.join("topology")).unwrap();
+        fs::write(
+            cpu.join("topology/physical_package_id"),
+            format!("{package}\n"),
+        )
+        .unwrap();
+        fs::write(freq.join("scaling_cur_freq"), format!("{cur_khz}\n")).unwrap();
+        fs::write(freq.join("scaling_min_freq"), "800000\n").unwrap();
+        fs::write(freq.join("scaling_max_freq"), "3500000\n").unwrap();
+        fs::write(freq.join("scaling_governor"), format!("{governor}\n")).unwrap();
+        write_throttle(sys_root, core, 0, 0);
+    }
+
+    fn write_throttle(sys_root: &Path, core: u32, core_count: u64, package_count: u64) {
+        let t = sys_root.join(format!("devices/system/cpu/cpu{core}/thermal_throttle"));
+        fs::create_dir_all(&t).unwrap();
+        fs::write(t.join("core_throttle_count"), format!("{core_count}\n")).unwrap();
+        fs::write(
+            t.join("package_throttle_count"),
+            format!("{package_count}\n"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_read_samples_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cpu(dir.path(), 1, 0, 2_000_000, "performance");
+        write_cpu(dir.path(), 0, 0, 3_000_000, "performance");
+        // non-cpu entries are ignored
+        fs::create_dir_all(dir.path().join("devices/system/cpu/cpufreq")).unwrap();
+
+        let samples = CpuFreqCollector::read_samples(dir.path()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].core, 0);
+        assert_eq!(samples[0].cur_khz, Some(3_000_000));
+        assert_eq!(samples[1].max_khz, Some(3_500_000));
+        assert_eq!(samples[1].governor.as_deref(), Some("performance"));
+    }
+
+    #[test]
+    fn test_throttle_events_dedupe_package_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cpu(dir.path(), 0, 0, 2_000_000, "performance");
+        write_cpu(dir.path(), 1, 0, 2_000_000, "performance");
+        write_cpu(dir.path(), 2, 1, 2_000_000, "performance");
+        let prev = CpuFreqCollector::read_samples(dir.path()).unwrap();
+
+        // cpu0 throttled 3 times, package 0 throttled 5 times (seen on cpu0 and cpu1)
+        write_throttle(dir.path(), 0, 3, 5);
+        write_throttle(dir.path(), 1, 0, 5);
+        let curr = CpuFreqCollector::read_samples(dir.path()).unwrap();
+        assert_eq!(CpuFreqCollector::throttle_events(&prev, &curr), 8);
+    }
+
+    #[tokio::test]
+    async fn test_power_saving_governor_degrades() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cpu(dir.path(), 0, 0, 800_000, "powersave");
+        write_cpu(dir.path(), 1, 0, 3_000_000, "performance");
+        let mut collector = CpuFreqCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Degraded);
+        match result.payload {
+            MetricPayload::CpuFreq(s) => assert_eq!(s.power_saving_cores, vec![0]),
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_powersave_under_acpi_cpufreq_degrades() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cpu(dir.path(), 0, 0, 800_000, "powersave");
+        write_driver(dir.path(), 0, "acpi-cpufreq");
+        let mut collector = CpuFreqCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Degraded);
+        match result.payload {
+            MetricPayload::CpuFreq(s) => {
+                assert_eq!(s.power_saving_cores, vec![0]);
+                assert_eq!(s.cores[0].driver.as_deref(), Some("acpi-cpufreq"));
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_powersave_under_hardware_pstate_is_healthy() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cpu(dir.path(), 0, 0, 3_000_000, "powersave");
+        write_driver(dir.path(), 0, "intel_pstate");
+        write_cpu(dir.path(), 1, 0, 3_000_000, "powersave");
+        write_driver(dir.path(), 1, "amd-pstate-epp");
+        let mut collector = CpuFreqCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::CpuFreq(s) => assert!(s.power_saving_cores.is_empty()),
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_conservative_is_power_saving_under_any_driver() {
+        assert!(CpuFreqCollector::is_power_saving(
+            Some("conservative"),
+            Some("intel_pstate")
+        ));
+        assert!(!CpuFreqCollector::is_power_saving(
+            Some("performance"),
+            Some("acpi-cpufreq")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_throttling_between_collections_degrades() {
+        let dir = tempfile::tempdir().unwrap();
+        write_cpu(dir.path(), 0, 0, 3_000_000, "performance");
+        let mut collector = CpuFreqCollector::new(dir.path());
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Healthy
+        );
+
+        write_throttle(dir.path(), 0, 2, 0);
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Degraded);
+        assert!(result.message.contains("throttle_events=2"));
+    }
+
+    #[tokio::test]
+    async fn test_vm_without_cpufreq_is_healthy() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("devices/system/cpu/cpu0")).unwrap();
+        let mut collector = CpuFreqCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("cpufreq not exposed"));
+    }
+}
@@ -0,0 +1,488 @@
+use super::{
+    CheckStatus, CollectionResult, Collector, InterruptRate, InterruptsSnapshot, MetricPayload,
+};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::fs;
+
+/// A device or softirq is imbalanced when one CPU handles at least this
+/// share of its interrupts...
+const IMBALANCE_TOP_CPU_SHARE_PCT: f64 = 80.0;
+/// ...and it is busy enough for that to matter.
+const IMBALANCE_MIN_RATE_PER_SEC: f64 = 1000.0;
+/// Softirqs whose distribution follows device interrupts. TIMER, SCHED,
+/// RCU etc. are raised on every CPU by design and are not checked.
+const BALANCED_SOFTIRQS: &[&str] = &["NET_RX", "NET_TX", "BLOCK"];
+/// Per-queue suffixes stripped to group a multi-queue device's IRQs,
+/// e.g. eth0-TxRx-3, virtio1-input.0 and mlx5_comp3@pci:0000:00:02.0.
+const QUEUE_WORDS: &[&str] = &["txrx", "rx", "tx", "input", "output", "queue", "fp", "comp"];
+
+/// Interrupt and softirq distribution collector reading /proc/interrupts
+/// and /proc/softirqs.
+pub struct InterruptsCollector {
+    proc_root: PathBuf,
+    prev: Option<(Instant, InterruptSample)>,
+}
+
+/// Cumulative per-CPU counters. Rows are keyed by device (numeric IRQs
+/// grouped by device name) or softirq name; columns follow `cpus`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct InterruptSample {
+    cpus: Vec<u32>,
+    devices: BTreeMap<String, Vec<u64>>,
+    softirqs: BTreeMap<String, Vec<u64>>,
+}
+
+/// One parsed table: online CPU ids from the header, then one row per
+/// line as (label, per-CPU counts, trailing description).
+type CounterTable<'a> = (Vec<u32>, Vec<(&'a str, Vec<u64>, &'a str)>);
+
+impl InterruptsCollector {
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            prev: None,
+        }
+    }
+
+    /// Parse the shared layout of /proc/interrupts and /proc/softirqs: a
+    /// `CPU0 CPU1 ...` header (offline CPUs are missing), then
+    /// `<label>: <count per cpu> [description]`.
+    fn parse_table<'a>(path: &str, content: &'a str) -> Result<CounterTable<'a>, CollectorError> {
+        let mut lines = content.lines();
+        let header = lines.next().unwrap_or_default();
+        let cpus: Vec<u32> = header
+            .split_whitespace()
+            .map(|col| col.strip_prefix("CPU").and_then(|n| n.parse().ok()))
+            .collect::<Option<_>>()
+            .filter(|cpus: &Vec<u32>| !cpus.is_empty())
+            .ok_or_else(|| CollectorError::ParseError {
+                path: path.into(),
+                field: "header".into(),
+                raw: header.to_string(),
+            })?;
+
+        let mut rows = Vec::new();
+        for line in lines {
+            let Some((label, rest)) = line.split_once(':') else {
+                continue;
+            };
+            let mut counts = Vec::with_capacity(cpus.len());
+            let mut rest = rest.trim_start();
+            while counts.len() < cpus.len() {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                match rest[..end].parse::<u64>() {
+                    Ok(n) => counts.push(n),
+                    Err(_) => break,
+                }
+                rest = rest[end..].trim_start();
+            }
+            rows.push((label.trim(), counts, rest.trim_end()));
+        }
+        Ok((cpus, rows))
+    }
+
+    /// Device name for an IRQ from the last word of its description, with
+    /// per-queue suffixes removed so all queues of a NIC or NVMe drive
+    /// group together. A bus address after `@` (mlx4/mlx5) is kept, so two
+    /// NICs of the same driver stay apart.
+    fn device_name(irq: &str, description: &str) -> String {
+        let Some(token) = description.split_whitespace().last() else {
+            return format!("irq{irq}");
+        };
+        let (mut base, address) = match token.split_once('@') {
+            Some((base, address)) => (base, Some(address)),
+            None => (token, None),
+        };
+        // drop `-<word><n>` / `_<word><n>` suffixes while the word is a
+        // queue word or empty (a bare queue number)
+        loop {
+            let trimmed = base.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            let Some(pos) = trimmed.rfind(['-', '_']) else {
+                break;
+            };
+            let word = trimmed[pos + 1..].to_ascii_lowercase();
+            if !word.is_empty() && !QUEUE_WORDS.contains(&word.as_str()) {
+                break;
+            }
+            base = &trimmed[..pos];
+        }
+        let name = match address {
+            Some(address) => format!("{base}@{address}"),
+            None => base.to_string(),
+        };
+
+        // nvme0q3 -> nvme0
+        if let Some((dev, queue)) = name.rsplit_once('q') {
+            if dev.ends_with(|c: char| c.is_ascii_digit())
+                && !queue.is_empty()
+                && queue.chars().all(|c| c.is_ascii_digit())
+            {
+                return dev.to_string();
+            }
+        }
+        name
+    }
+
+    fn parse_sample(
+        interrupts: &str,
+        softirqs: &str,
+        proc_root: &str,
+    ) -> Result<InterruptSample, CollectorError> {
+        let (cpus, irq_rows) = Self::parse_table(&format!("{proc_root}/interrupts"), interrupts)?;
+        let (softirq_cpus, softirq_rows) =
+            Self::parse_table(&format!("{proc_root}/softirqs"), softirqs)?;
+
+        let mut sample = InterruptSample {
+            cpus,
+            ..InterruptSample::default()
+        };
+        // named rows (LOC, NMI, RES, ...) are per-CPU architecture interrupts
+        for (irq, counts, description) in irq_rows {
+            if irq.parse::<u32>().is_err() {
+                continue;
+            }
+            let row = sample
+                .devices
+                .entry(Self::device_name(irq, description))
+                .or_insert_with(|| vec![0; sample.cpus.len()]);
+            for (total, count) in row.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+        // /proc/softirqs lists possible rather than online CPUs on some
+        // kernels; keep only the columns present in /proc/interrupts
+        for (name, counts, _) in softirq_rows {
+            let row = sample
+                .cpus
+                .iter()
+                .map(|cpu| {
+                    softirq_cpus
+                        .iter()
+                        .position(|c| c == cpu)
+                        .and_then(|i| counts.get(i).copied())
+                        .unwrap_or(0)
+                })
+                .collect();
+            sample.softirqs.insert(name.to_string(), row);
+        }
+        Ok(sample)
+    }
+
+    /// Per-CPU rates for every row present in both samples, busiest first.
+    /// Rows with no activity in the interval are dropped.
+    fn rates(
+        prev: &BTreeMap<String, Vec<u64>>,
+        current: &BTreeMap<String, Vec<u64>>,
+        cpus: &[u32],
+        elapsed_secs: f64,
+    ) -> Vec<InterruptRate> {
+        let mut rates: Vec<InterruptRate> = current
+            .iter()
+            .filter_map(|(name, counts)| {
+                let before = prev.get(name)?;
+                let per_cpu_per_sec: Vec<f64> = counts
+                    .iter()
+                    .zip(before)
+                    .map(|(c, p)| c.saturating_sub(*p) as f64 / elapsed_secs)
+                    .collect();
+                let total_per_sec: f64 = per_cpu_per_sec.iter().sum();
+                if total_per_sec <= 0.0 {
+                    return None;
+                }
+                let (top, top_rate) = per_cpu_per_sec
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .unwrap_or_default();
+                Some(InterruptRate {
+                    name: name.clone(),
+                    total_per_sec,
+                    per_cpu_per_sec,
+                    top_cpu: cpus[top],
+                    top_cpu_share_pct: top_rate / total_per_sec * 100.0,
+                })
+            })
+            .collect();
+        rates.sort_by(|a, b| b.total_per_sec.total_cmp(&a.total_per_sec));
+        rates
+    }
+
+    fn is_imbalanced(rate: &InterruptRate, num_cpus: usize) -> bool {
+        num_cpus > 1
+            && rate.total_per_sec >= IMBALANCE_MIN_RATE_PER_SEC
+            && rate.top_cpu_share_pct >= IMBALANCE_TOP_CPU_SHARE_PCT
+    }
+
+    fn evaluate(snapshot: &InterruptsSnapshot) -> CheckStatus {
+        if snapshot.imbalanced.is_empty() {
+            CheckStatus::Healthy
+        } else {
+            CheckStatus::Degraded
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for InterruptsCollector {
+    fn name(&self) -> &'static str {
+        "interrupts"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let read = |file: &'static str| {
+            let path = self.proc_root.join(file);
+            async move {
+                fs::read_to_string(&path)
+                    .await
+                    .map_err(|e| CollectorError::ProcReadError {
+                        path: path.display().to_string(),
+                        source: e,
+                    })
+            }
+        };
+        let interrupts = read("interrupts").await?;
+        let softirqs = read("softirqs").await?;
+        let now = Instant::now();
+        let sample = Self::parse_sample(
+            &interrupts,
+            &softirqs,
+            &self.proc_root.display().to_string(),
+        )?;
+
+        let mut snapshot = InterruptsSnapshot {
+            cpus: sample.cpus.clone(),
+            ..InterruptsSnapshot::default()
+        };
+        match &self.prev {
+            None => snapshot.discarded_reason = Some("baseline sample".into()),
+            // per-CPU columns only line up if the online set is unchanged
+            Some((_, prev)) if prev.cpus != sample.cpus => {
+                snapshot.discarded_reason = Some("cpu hotplug".into())
+            }
+            Some((prev_at, prev)) => {
+                let elapsed = now.duration_since(*prev_at).as_secs_f64();
+                if elapsed > 0.0 {
+                    snapshot.devices =
+                        Self::rates(&prev.devices, &sample.devices, &sample.cpus, elapsed);
+                    snapshot.softirqs =
+                        Self::rates(&prev.softirqs, &sample.softirqs, &sample.cpus, elapsed);
+                }
+            }
+        }
+        let num_cpus = sample.cpus.len();
+        snapshot.imbalanced = snapshot
+            .devices
+            .iter()
+            .chain(
+                snapshot
+                    .softirqs
+                    .iter()
+                    .filter(|s| BALANCED_SOFTIRQS.contains(&s.name.as_str())),
+            )
+            .filter(|r| Self::is_imbalanced(r, num_cpus))
+            .map(|r| r.name.clone())
+            .collect();
+        self.prev = Some((now, sample));
+
+        let status = Self::evaluate(&snapshot);
+        let message = match &snapshot.discarded_reason {
+            Some(reason) => format!("interval discarded: {reason}"),
+            None => {
+                let softirq = |name: &str| {
+                    snapshot
+                        .softirqs
+                        .iter()
+                        .find(|s| s.name == name)
+                        .map_or(0.0, |s| s.total_per_sec)
+                };
+                let imbalanced: Vec<String> = snapshot
+                    .imbalanced
+                    .iter()
+                    .filter_map(|name| {
+                        snapshot
+                            .devices
+                            .iter()
+                            .chain(&snapshot.softirqs)
+                            .find(|r| &r.name == name)
+                    })
+                    .map(|r| {
+                        format!(
+                            "{} ({:.0}% on cpu{})",
+                            r.name, r.top_cpu_share_pct, r.top_cpu
+                        )
+                    })
+                    .collect();
+                format!(
+                    "NET_RX={:.0}/s BLOCK={:.0}/s TIMER={:.0}/s imbalanced=[{}]",
+                    softirq("NET_RX"),
+                    softirq("BLOCK"),
+                    softirq("TIMER"),
+                    imbalanced.join(", ")
+                )
+            }
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Interrupts(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_INTERRUPTS: &str = "\
+           CPU0       CPU1       CPU2       CPU3
+  0:         44          0          0          0   IO-APIC   2-edge      timer
+ 24:       1000          0          0          0  PCI-MSI 524288-edge      eth0-TxRx-0
+ 25:        500          0          0          0  PCI-MSI 524289-edge      eth0-TxRx-1
+ 26:         10         20         30         40  PCI-MSI 1048576-edge      nvme0q1
+ 27:         10         20         30         40  PCI-MSI 1048577-edge      nvme0q2
+NMI:          1          2          3          4   Non-maskable interrupts
+LOC:     100000     100000     100000     100000   Local timer interrupts
+ERR:          0
+MIS:          0";
+
+    const SAMPLE_SOFTIRQS: &str = "\
+                    CPU0       CPU1       CPU2       CPU3
+          HI:          0          0          0          0
+       TIMER:       1000       1000       1000       1000
+      NET_TX:          5          0          0          0
+      NET_RX:       2000          0          0          0
+       BLOCK:        100        100        100        100";
+
+    fn sample() -> InterruptSample {
+        InterruptsCollector::parse_sample(SAMPLE_INTERRUPTS, SAMPLE_SOFTIRQS, "/proc").unwrap()
+    }
+
+    #[test]
+    fn test_device_name_groups_queues() {
+        let name = InterruptsCollector::device_name;
+        assert_eq!(name("24", "PCI-MSI 524288-edge      eth0-TxRx-0"), "eth0");
+        assert_eq!(
+            name("11", "PCI-MSIX-0000:00:03.0 1-edge virtio1-input.0"),
+            "virtio1"
+        );
+        assert_eq!(name("26", "PCI-MSI 1048576-edge nvme0q1"), "nvme0");
+        assert_eq!(name("1", "IO-APIC 1-edge i8042"), "i8042");
+        assert_eq!(name("7", ""), "irq7");
+        assert_eq!(
+            name(
+                "40",
+                "PCI-MSIX-7870:00:02.0 1-edge mlx5_comp7@pci:7870:00:02.0"
+            ),
+            "mlx5@pci:7870:00:02.0"
+        );
+        assert_eq!(
+            name("41", "PCI-MSI 1-edge mlx5_async0@pci:7870:00:02.0"),
+            "mlx5_async0@pci:7870:00:02.0"
+        );
+        assert_eq!(
+            name("42", "PCI-MSI 1-edge mlx4-3@0002:00:02.0"),
+            "mlx4@0002:00:02.0"
+        );
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let s = sample();
+        assert_eq!(s.cpus, vec![0, 1, 2, 3]);
+        assert_eq!(s.devices["eth0"], vec![1500, 0, 0, 0]);
+        assert_eq!(s.devices["nvme0"], vec![20, 40, 60, 80]);
+        // architecture rows are not devices
+        assert!(!s.devices.contains_key("Local timer interrupts"));
+        assert_eq!(s.softirqs["NET_RX"], vec![2000, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_parse_sample_with_offline_cpu() {
+        let interrupts = "           CPU0       CPU2\n 24:   10   20   PCI-MSI 1-edge eth0\n";
+        let softirqs = "    CPU0   CPU1   CPU2\n NET_RX:   1   2   3\n";
+        let s = InterruptsCollector::parse_sample(interrupts, softirqs, "/proc").unwrap();
+        assert_eq!(s.cpus, vec![0, 2]);
+        assert_eq!(s.softirqs["NET_RX"], vec![1, 3]);
+    }
+
+    #[test]
+    fn test_rates_and_imbalance() {
+        let prev = sample();
+        let mut curr = prev.clone();
+        // eth0: 5000 interrupts in 1s, all on cpu0
+        curr.devices.get_mut("eth0").unwrap()[0] += 5000;
+        // nvme0: evenly spread
+        for c in curr.devices.get_mut("nvme0").unwrap() {
+            *c += 1000;
+        }
+
+        let rates = InterruptsCollector::rates(&prev.devices, &curr.devices, &curr.cpus, 1.0);
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].name, "eth0");
+        assert_eq!(rates[0].top_cpu, 0);
+        assert!((rates[0].top_cpu_share_pct - 100.0).abs() < 1e-9);
+        assert!(InterruptsCollector::is_imbalanced(&rates[0], 4));
+        assert!(!InterruptsCollector::is_imbalanced(&rates[1], 4));
+        // a single CPU cannot be imbalanced
+        assert!(!InterruptsCollector::is_imbalanced(&rates[0], 1));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_mlx5_queues_are_not_imbalanced() {
+        // Azure accelerated networking: one completion queue per CPU, each
+        // pinned to its CPU by design
+        let interrupts = |n: u64| {
+            format!(
+                "           CPU0       CPU1       CPU2       CPU3
+ 40:   {n}   0   0   0   PCI-MSIX-7870:00:02.0 0-edge mlx5_comp0@pci:7870:00:02.0
+ 41:   0   {n}   0   0   PCI-MSIX-7870:00:02.0 1-edge mlx5_comp1@pci:7870:00:02.0
+ 42:   0   0   {n}   0   PCI-MSIX-7870:00:02.0 2-edge mlx5_comp2@pci:7870:00:02.0
+ 43:   0   0   0   {n}   PCI-MSIX-7870:00:02.0 3-edge mlx5_comp3@pci:7870:00:02.0
+"
+            )
+        };
+        let softirqs = "    CPU0   CPU1   CPU2   CPU3\n NET_RX:   0   0   0   0\n";
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("interrupts"), interrupts(0)).unwrap();
+        std::fs::write(dir.path().join("softirqs"), softirqs).unwrap();
+        let mut collector = InterruptsCollector::new(dir.path());
+        collector.collect().await.unwrap();
+
+        std::fs::write(dir.path().join("interrupts"), interrupts(1_000_000)).unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Interrupts(s) => {
+                assert!(s.imbalanced.is_empty());
+                assert_eq!(s.devices.len(), 1);
+                assert_eq!(s.devices[0].name, "mlx5@pci:7870:00:02.0");
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("interrupts"), SAMPLE_INTERRUPTS).unwrap();
+        std::fs::write(dir.path().join("softirqs"), SAMPLE_SOFTIRQS).unwrap();
+        let mut collector = InterruptsCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("baseline sample"));
+
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("imbalanced=[]"));
+    }
+}
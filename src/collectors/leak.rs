@@ -0,0 +1,538 @@
+use super::process::ProcessCollector;
+use super::{
+    CheckStatus, CollectionResult, Collector, MemoryLeakSnapshot, MetricPayload, ProcessMemoryTrend,
+};
+use crate::errors::CollectorError;
+use crate::selectors::MonitoredProcesses;
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Minimum goodness of fit for growth to count as steady rather than a
+/// burst (buffer pool warm-up, a large sort).
+const MIN_R_SQUARED: f64 = 0.8;
+/// Fewer samples than this cannot describe a trend.
+const MIN_SAMPLES: usize = 3;
+/// smaps_rollup walks every mapping of the process under its mmap lock,
+/// which is costly for a large mysqld, so PSS is read at most this often
+/// while RSS is sampled every cycle.
+const PSS_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Default leak thresholds, shared by [`LeakThresholds::default`] and the CLI.
+pub const DEFAULT_WINDOW_MINS: u64 = 60;
+pub const DEFAULT_SUSTAINED_MINS: u64 = 30;
+pub const DEFAULT_MIN_GROWTH_MB_PER_HOUR: f64 = 50.0;
+/// Longest trend window accepted; samples are kept for the whole window.
+pub const MAX_WINDOW_MINS: u64 = 7 * 24 * 60;
+
+/// How much growth, for how long, makes a leak.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeakThresholds {
+    /// Rolling window the trend line is fitted over.
+    pub window: Duration,
+    /// Growth must be visible over at least this much of the window.
+    pub sustained: Duration,
+    pub min_growth_bytes_per_hour: f64,
+}
+
+impl Default for LeakThresholds {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(DEFAULT_WINDOW_MINS * 60),
+            sustained: Duration::from_secs(DEFAULT_SUSTAINED_MINS * 60),
+            min_growth_bytes_per_hour: DEFAULT_MIN_GROWTH_MB_PER_HOUR * 1024.0 * 1024.0,
+        }
+    }
+}
+
+/// Memory leak detection for the monitored processes: keeps a rolling
+/// window of RSS/PSS samples per process and fits a trend line over it.
+pub struct LeakCollector {
+    monitored: MonitoredProcesses,
+    thresholds: LeakThresholds,
+    history: HashMap<u32, ProcessHistory>,
+}
+
+/// Samples for one process instance. `starttime` tells a restarted process
+/// that reused the PID apart from the original.
+#[derive(Debug, Clone)]
+struct ProcessHistory {
+    starttime: u64,
+    comm: String,
+    samples: VecDeque<MemSample>,
+    /// When smaps_rollup was last read for this process.
+    last_pss_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MemSample {
+    at: Instant,
+    rss_bytes: u64,
+    /// None between PSS reads, and when smaps_rollup is missing (pre-4.14)
+    /// or not readable.
+    pss_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ProcessReading {
+    pid: u32,
+    comm: String,
+    starttime: u64,
+    rss_bytes: u64,
+    pss_bytes: Option<u64>,
+}
+
+impl LeakCollector {
+    pub fn new(monitored: MonitoredProcesses, thresholds: LeakThresholds) -> Self {
+        Self {
+            monitored,
+            thresholds,
+            history: HashMap::new(),
+        }
+    }
+
+    /// Value of a `Key:   123 kB` line, in bytes.
+    fn kb_field(content: &str, key: &str) -> Option<u64> {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    }
+
+    /// One reading, or None if the process exited while being read.
+    /// smaps_rollup is only read when `read_pss` is set.
+    fn read_process(proc_root: &Path, pid: u32, read_pss: bool) -> Option<ProcessReading> {
+        let dir = proc_root.join(pid.to_string());
+        let stat = fs::read_to_string(dir.join("stat")).ok()?;
+        let stat =
+            ProcessCollector::parse_stat(&dir.join("stat").display().to_string(), &stat).ok()?;
+        let status = fs::read_to_string(dir.join("status")).ok()?;
+        Some(ProcessReading {
+            pid,
+            comm: stat.comm,
+            starttime: stat.starttime,
+            // kernel threads have no VmRSS
+            rss_bytes: Self::kb_field(&status, "VmRSS").unwrap_or(0),
+            pss_bytes: read_pss
+                .then(|| fs::read_to_string(dir.join("smaps_rollup")).ok())
+                .flatten()
+                .and_then(|rollup| Self::kb_field(&rollup, "Pss")),
+        })
+    }
+
+    fn read_processes(pids: Vec<(u32, bool)>, proc_root: &Path) -> Vec<ProcessReading> {
+        pids.into_iter()
+            .filter_map(|(pid, read_pss)| Self::read_process(proc_root, pid, read_pss))
+            .collect()
+    }
+
+    /// Whether PSS should be read for `pid` this cycle.
+    fn pss_due(&self, pid: u32, now: Instant) -> bool {
+        self.history
+            .get(&pid)
+            .and_then(|h| h.last_pss_at)
+            .is_none_or(|at| now.duration_since(at) >= PSS_INTERVAL)
+    }
+
+    /// Least-squares fit of `y = a + b x`, returning the slope and R².
+    pub(crate) fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+        if points.len() < MIN_SAMPLES {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+        for (x, y) in points {
+            sxx += (x - mean_x).powi(2);
+            sxy += (x - mean_x) * (y - mean_y);
+            syy += (y - mean_y).powi(2);
+        }
+        if sxx == 0.0 {
+            return None;
+        }
+        let slope = sxy / sxx;
+        // a perfectly flat series has no trend to explain
+        let r_squared = if syy == 0.0 {
+            0.0
+        } else {
+            sxy * sxy / (sxx * syy)
+        };
+        Some((slope, r_squared))
+    }
+
+    /// Add readings taken at `now`, restart the history of processes whose
+    /// PID was reused, and forget processes that are gone.
+    fn record(&mut self, now: Instant, readings: &[ProcessReading]) {
+        self.history
+            .retain(|pid, _| readings.iter().any(|r| r.pid == *pid));
+        for r in readings {
+            let history = self.history.entry(r.pid).or_insert_with(|| ProcessHistory {
+                starttime: r.starttime,
+                comm: r.comm.clone(),
+                samples: VecDeque::new(),
+                last_pss_at: None,
+            });
+            if history.starttime != r.starttime {
+                history.starttime = r.starttime;
+                history.samples.clear();
+                history.last_pss_at = None;
+            }
+            history.comm = r.comm.clone();
+            if r.pss_bytes.is_some() {
+                history.last_pss_at = Some(now);
+            }
+            history.samples.push_back(MemSample {
+                at: now,
+                rss_bytes: r.rss_bytes,
+                pss_bytes: r.pss_bytes,
+            });
+            while history
+                .samples
+                .front()
+                .is_some_and(|s| now.duration_since(s.at) > self.thresholds.window)
+            {
+                history.samples.pop_front();
+            }
+        }
+    }
+
+    fn trend(
+        &self,
+        pid: u32,
+        history: &ProcessHistory,
+        mem_available_bytes: Option<u64>,
+    ) -> ProcessMemoryTrend {
+        let samples = &history.samples;
+        // PSS splits shared pages fairly, so the trend is fitted over the
+        // (sparser) PSS reads whenever smaps_rollup is readable, and over
+        // the RSS of every sample only when it is not.
+        let use_pss = samples.iter().any(|s| s.pss_bytes.is_some());
+        let series: Vec<(Instant, u64)> = samples
+            .iter()
+            .filter_map(|s| {
+                if use_pss {
+                    s.pss_bytes.map(|pss| (s.at, pss))
+                } else {
+                    Some((s.at, s.rss_bytes))
+                }
+            })
+            .collect();
+        let first = series.first().map(|(at, _)| *at);
+        let span = match (first, series.last()) {
+            (Some(first), Some((last, _))) => last.duration_since(first),
+            _ => Duration::ZERO,
+        };
+        let points: Vec<(f64, f64)> = series
+            .iter()
+            .map(|(at, bytes)| {
+                let secs = first.map_or(0.0, |f| at.duration_since(f).as_secs_f64());
+                (secs, *bytes as f64)
+            })
+            .collect();
+        let fit = Self::linear_fit(&points);
+        let growth_bytes_per_hour = fit.map(|(slope, _)| slope * 3600.0);
+
+        let leaking = fit.is_some_and(|(_, r2)| r2 >= MIN_R_SQUARED)
+            && span >= self.thresholds.sustained
+            && growth_bytes_per_hour
+                .is_some_and(|g| g >= self.thresholds.min_growth_bytes_per_hour);
+        let exhaustion_eta_secs = match (growth_bytes_per_hour, mem_available_bytes) {
+            (Some(g), Some(available)) if leaking && g > 0.0 => {
+                Some((available as f64 / g * 3600.0) as u64)
+            }
+            _ => None,
+        };
+
+        ProcessMemoryTrend {
+            pid,
+            name: history.comm.clone(),
+            rss_bytes: samples.back().map_or(0, |s| s.rss_bytes),
+            // the latest PSS read, which may be a few cycles old
+            pss_bytes: samples.iter().rev().find_map(|s| s.pss_bytes),
+            samples: samples.len(),
+            window_secs: span.as_secs(),
+            trend_source: if use_pss { "pss" } else { "rss" }.to_string(),
+            growth_bytes_per_hour,
+            r_squared: fit.map(|(_, r2)| r2),
+            leaking,
+            exhaustion_eta_secs,
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for LeakCollector {
+    fn name(&self) -> &'static str {
+        "memory_leak"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let now = Instant::now();
+        let pids: Vec<(u32, bool)> = self
+            .monitored
+            .pids()
+            .into_iter()
+            .map(|pid| (pid, self.pss_due(pid, now)))
+            .collect();
+        let proc_root = self.monitored.proc_root().to_path_buf();
+        let readings = tokio::task::spawn_blocking(move || Self::read_processes(pids, &proc_root))
+            .await
+            .map_err(|e| CollectorError::ProcReadError {
+                path: self.monitored.proc_root().display().to_string(),
+                source: std::io::Error::other(e),
+            })?;
+        let meminfo_path = self.monitored.proc_root().join("meminfo");
+        let mem_available_bytes = tokio::fs::read_to_string(&meminfo_path)
+            .await
+            .map_err(|e| CollectorError::ProcReadError {
+                path: meminfo_path.display().to_string(),
+                source: e,
+            })
+            .map(|content| Self::kb_field(&content, "MemAvailable"))?;
+
+        self.record(now, &readings);
+        let mut processes: Vec<ProcessMemoryTrend> = self
+            .history
+            .iter()
+            .map(|(pid, history)| self.trend(*pid, history, mem_available_bytes))
+            .collect();
+        processes.sort_by_key(|p| p.pid);
+        let snapshot = MemoryLeakSnapshot {
+            mem_available_bytes,
+            processes,
+        };
+
+        let leaking: Vec<String> = snapshot
+            .processes
+            .iter()
+            .filter(|p| p.leaking)
+            .map(|p| {
+                format!(
+                    "{} (pid {}) +{:.0} MB/h{}",
+                    p.name,
+                    p.pid,
+                    p.growth_bytes_per_hour.unwrap_or(0.0) / (1024.0 * 1024.0),
+                    p.exhaustion_eta_secs
+                        .map(|eta| format!(", memory exhausted in ~{}m", eta / 60))
+                        .unwrap_or_default()
+                )
+            })
+            .collect();
+        let (status, message) = if leaking.is_empty() {
+            (
+                CheckStatus::Healthy,
+                format!(
+                    "no steady memory growth in {} monitored process(es)",
+                    snapshot.processes.len()
+                ),
+            )
+        } else {
+            (
+                CheckStatus::Degraded,
+                format!("steady memory growth: {}", leaking.join("; ")),
+            )
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::MemoryLeak(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selectors::parse_selector;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn reading(pid: u32, starttime: u64, rss_bytes: u64) -> ProcessReading {
+        ProcessReading {
+            pid,
+            comm: "mysqld".into(),
+            starttime,
+            rss_bytes,
+            pss_bytes: None,
+        }
+    }
+
+    fn collector() -> LeakCollector {
+        LeakCollector::new(
+            MonitoredProcesses::new(Vec::new(), "/proc"),
+            LeakThresholds::default(),
+        )
+    }
+
+    #[test]
+    fn test_kb_field() {
+        let status = "Name:\tmysqld\nVmRSS:\t  2048 kB\n";
+        assert_eq!(LeakCollector::kb_field(status, "VmRSS"), Some(2048 * 1024));
+        assert_eq!(LeakCollector::kb_field(status, "VmSwap"), None);
+    }
+
+    #[test]
+    fn test_linear_fit() {
+        let (slope, r2) =
+            LeakCollector::linear_fit(&[(0.0, 10.0), (1.0, 12.0), (2.0, 14.0)]).unwrap();
+        assert!((slope - 2.0).abs() < 1e-9);
+        assert!((r2 - 1.0).abs() < 1e-9);
+        assert_eq!(
+            LeakCollector::linear_fit(&[(0.0, 5.0), (1.0, 5.0), (2.0, 5.0)]),
+            Some((0.0, 0.0))
+        );
+        assert!(LeakCollector::linear_fit(&[(0.0, 1.0), (1.0, 2.0)]).is_none());
+    }
+
+    #[test]
+    fn test_steady_growth_is_a_leak() {
+        let mut c = collector();
+        let start = Instant::now();
+        // +100 MiB per hour, sampled every 5 minutes for 40 minutes
+        for i in 0..=8u64 {
+            let at = start + Duration::from_secs(i * 300);
+            c.record(at, &[reading(7, 100, 1024 * MIB + i * 100 * MIB / 12)]);
+        }
+        let trend = c.trend(7, &c.history[&7], Some(1024 * MIB));
+        assert!(trend.leaking);
+        assert_eq!(trend.trend_source, "rss");
+        assert!((trend.growth_bytes_per_hour.unwrap() / MIB as f64 - 100.0).abs() < 0.1);
+        // 1 GiB available at 100 MiB/h
+        let eta = trend.exhaustion_eta_secs.unwrap();
+        assert!((eta as i64 - 36864).abs() <= 1);
+    }
+
+    #[test]
+    fn test_short_or_noisy_growth_is_not_a_leak() {
+        let mut c = collector();
+        let start = Instant::now();
+        // steady growth, but only for 10 minutes
+        for i in 0..=2u64 {
+            c.record(
+                start + Duration::from_secs(i * 300),
+                &[reading(7, 100, (1000 + i * 50) * MIB)],
+            );
+        }
+        assert!(!c.trend(7, &c.history[&7], None).leaking);
+
+        // saw-tooth over 40 minutes
+        let mut c = collector();
+        for i in 0..=8u64 {
+            let rss = if i % 2 == 0 { 1000 } else { 1400 } * MIB;
+            c.record(
+                start + Duration::from_secs(i * 300),
+                &[reading(7, 100, rss)],
+            );
+        }
+        assert!(!c.trend(7, &c.history[&7], None).leaking);
+    }
+
+    #[test]
+    fn test_history_reset_on_pid_reuse_and_exit() {
+        let mut c = collector();
+        let start = Instant::now();
+        c.record(start, &[reading(7, 100, MIB), reading(8, 200, MIB)]);
+        c.record(start + Duration::from_secs(60), &[reading(7, 100, MIB)]);
+        assert!(!c.history.contains_key(&8));
+        assert_eq!(c.history[&7].samples.len(), 2);
+
+        c.record(start + Duration::from_secs(120), &[reading(7, 555, MIB)]);
+        assert_eq!(c.history[&7].samples.len(), 1);
+    }
+
+    #[test]
+    fn test_window_drops_old_samples() {
+        let mut c = LeakCollector::new(
+            MonitoredProcesses::new(Vec::new(), "/proc"),
+            LeakThresholds {
+                window: Duration::from_secs(600),
+                ..LeakThresholds::default()
+            },
+        );
+        let start = Instant::now();
+        for i in 0..5u64 {
+            c.record(
+                start + Duration::from_secs(i * 300),
+                &[reading(7, 100, MIB)],
+            );
+        }
+        assert_eq!(c.history[&7].samples.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let p = dir.path().join("42");
+        fs::create_dir_all(&p).unwrap();
+        fs::write(p.join("comm"), "mysqld\n").unwrap();
+        fs::write(
+            p.join("stat"),
+            "42 (mysqld) S 1 42 42 0 -1 4194560 100 0 0 0 5 3 0 0 20 0 30 0 987654 123 456",
+        )
+        .unwrap();
+        fs::write(p.join("status"), "Name:\tmysqld\nVmRSS:\t  4096 kB\n").unwrap();
+        fs::write(p.join("smaps_rollup"), "Rss:  4096 kB\nPss:  3000 kB\n").unwrap();
+        fs::write(dir.path().join("meminfo"), "MemAvailable:  1000000 kB\n").unwrap();
+
+        let monitored =
+            MonitoredProcesses::new(vec![parse_selector("name:mysqld").unwrap()], dir.path());
+        monitored.refresh().await;
+        let mut collector = LeakCollector::new(monitored, LeakThresholds::default());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::MemoryLeak(s) => {
+                assert_eq!(s.mem_available_bytes, Some(1000000 * 1024));
+                assert_eq!(s.processes.len(), 1);
+                assert_eq!(s.processes[0].rss_bytes, 4096 * 1024);
+                assert_eq!(s.processes[0].pss_bytes, Some(3000 * 1024));
+                assert_eq!(s.processes[0].trend_source, "pss");
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        // the next cycle samples RSS but is too soon for another PSS read
+        fs::write(p.join("status"), "Name:\tmysqld\nVmRSS:\t  8192 kB\n").unwrap();
+        fs::write(p.join("smaps_rollup"), "Rss:  8192 kB\nPss:  7000 kB\n").unwrap();
+        let result = collector.collect().await.unwrap();
+        match result.payload {
+            MetricPayload::MemoryLeak(s) => {
+                assert_eq!(s.processes[0].samples, 2);
+                assert_eq!(s.processes[0].rss_bytes, 8192 * 1024);
+                assert_eq!(s.processes[0].pss_bytes, Some(3000 * 1024));
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_trend_fits_sparse_pss_reads() {
+        let mut c = collector();
+        let start = Instant::now();
+        // RSS every minute stays flat (shared pages), PSS every 5 minutes
+        // grows by 100 MiB per hour
+        for i in 0..=40u64 {
+            let at = start + Duration::from_secs(i * 60);
+            assert_eq!(c.pss_due(7, at), i % 5 == 0);
+            let mut r = reading(7, 100, 2048 * MIB);
+            if i % 5 == 0 {
+                r.pss_bytes = Some(1024 * MIB + i * 100 * MIB / 60);
+            }
+            c.record(at, &[r]);
+        }
+        let trend = c.trend(7, &c.history[&7], None);
+        assert_eq!(trend.trend_source, "pss");
+        assert_eq!(trend.samples, 41);
+        assert!(trend.leaking);
+        assert!((trend.growth_bytes_per_hour.unwrap() / MIB as f64 - 100.0).abs() < 0.1);
+    }
+}
@@ -1,20 +1,24 @@
-use super::*;
 use super::{CheckStatus, CollectionResult, Collector, MemorySnapshot, MetricPayload};
 use crate::errors::CollectorError;
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::fs;
 
 /// Memory metrics collector reading directly from /proc/meminfo.
-pub struct MemoryCollector;
+pub struct MemoryCollector {
+    proc_root: PathBuf,
+}
 
 impl MemoryCollector {
-    pub fn new() -> Self {
-        Self
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+        }
     }
 
     /// Parse /proc/meminfo into a key-value map of kB values.
-    fn parse_meminfo(content: &str) -> Result<HashMap<String, u64>, CollectorError> {
+    pub(crate) fn parse_meminfo(content: &str) -> Result<HashMap<String, u64>, CollectorError> {
         let mut map = HashMap::new();
         for line in content.lines() {
             let parts: Vec<&str> = line.split_whitespace().collect();
@@ -29,15 +33,24 @@ impl MemoryCollector {
     }
 
     /// Extract a required field from meminfo, converting kB -> bytes.
-    fn get_bytes(map: &HashMap<String, u64>, field: &str) -> Result<u64, CollectorError> {
+    fn get_bytes(
+        path: &str,
+        map: &HashMap<String, u64>,
+        field: &str,
+    ) -> Result<u64, CollectorError> {
         map.get(field)
             .map(|kb| kb * 1024)
             .ok_or_else(|| CollectorError::ParseError {
-                path: "/proc/meminfo".into(),
+                path: path.into(),
                 field: field.into(),
                 raw: "field not found".into(),
             })
     }
+
+    /// Extract an optional kB field, converting to bytes.
+    fn optional_bytes(map: &HashMap<String, u64>, field: &str) -> Option<u64> {
+        map.get(field).map(|kb| kb * 1024)
+    }
 }
 
 #[async_trait]
@@ -47,19 +60,22 @@ impl Collector for MemoryCollector {
     }
 
     async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
-        let content = fs::read_to_string("/proc/meminfo").await.map_err(|e| {
-            CollectorError::ProcReadError {
-                path: "/proc/meminfo".into(),
-                source: e,
-            }
-        })?;
+        let path = self.proc_root.join("meminfo");
+        let content =
+            fs::read_to_string(&path)
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: path.display().to_string(),
+                    source: e,
+                })?;
 
         let map = Self::parse_meminfo(&content)?;
 
-        let total = Self::get_bytes(&map, "MemTotal")?;
-        let available = Self::get_bytes(&map, "MemAvailable")?;
-        let swap_total = Self::get_bytes(&map, "SwapTotal").unwrap_or(0);
-        let swap_free = Self::get_bytes(&map, "SwapFree").unwrap_or(0);
+        let path = path.display().to_string();
+        let total = Self::get_bytes(&path, &map, "MemTotal")?;
+        let available = Self::get_bytes(&path, &map, "MemAvailable")?;
+        let swap_total = Self::get_bytes(&path, &map, "SwapTotal").unwrap_or(0);
+        let swap_free = Self::get_bytes(&path, &map, "SwapFree").unwrap_or(0);
 
         let used = total.saturating_sub(available);
         let swap_used = swap_total.saturating_sub(swap_free);
@@ -76,6 +92,22 @@ impl Collector for MemoryCollector {
             swap_total_bytes: swap_total,
             swap_used_bytes: swap_used,
             memory_pressure_pct: pressure_pct,
+            cached_bytes: Self::optional_bytes(&map, "Cached"),
+            buffers_bytes: Self::optional_bytes(&map, "Buffers"),
+            dirty_bytes: Self::optional_bytes(&map, "Dirty"),
+            writeback_bytes: Self::optional_bytes(&map, "Writeback"),
+            shmem_bytes: Self::optional_bytes(&map, "Shmem"),
+            slab_reclaimable_bytes: Self::optional_bytes(&map, "SReclaimable"),
+            slab_unreclaimable_bytes: Self::optional_bytes(&map, "SUnreclaim"),
+            committed_as_bytes: Self::optional_bytes(&map, "Committed_AS"),
+            commit_limit_bytes: Self::optional_bytes(&map, "CommitLimit"),
+            anon_pages_bytes: Self::optional_bytes(&map, "AnonPages"),
+            mapped_bytes: Self::optional_bytes(&map, "Mapped"),
+            huge_pages_total: map.get("HugePages_Total").copied(),
+            huge_pages_free: map.get("HugePages_Free").copied(),
+            huge_pages_rsvd: map.get("HugePages_Rsvd").copied(),
+            huge_pages_surp: map.get("HugePages_Surp").copied(),
+            hugepage_size_bytes: Self::optional_bytes(&map, "Hugepagesize"),
         };
 
         let status = if pressure_pct > 95.0 || (swap_total > 0 && swap_used > swap_total * 80 / 100)
@@ -87,7 +119,7 @@ impl Collector for MemoryCollector {
             CheckStatus::Healthy
         };
 
-        let message = format!(
+        let mut message = format!(
             "used={:.1}% ({}/{} MB) swap={}/{} MB",
             pressure_pct,
             used / (1024 * 1024),
@@ -95,6 +127,13 @@ impl Collector for MemoryCollector {
             swap_used / (1024 * 1024),
             swap_total / (1024 * 1024),
         );
+        if let (Some(cached), Some(anon)) = (snapshot.cached_bytes, snapshot.anon_pages_bytes) {
+            message.push_str(&format!(
+                " cached={} MB anon={} MB",
+                cached / (1024 * 1024),
+                anon / (1024 * 1024)
+            ));
+        }
 
         Ok(CollectionResult {
             check_name: self.name().to_string(),
@@ -102,6 +141,7 @@ impl Collector for MemoryCollector {
             message,
             metadata: HashMap::new(),
             latency_us: 0,
+            in_maintenance: false,
             payload: MetricPayload::Memory(snapshot),
         })
     }
@@ -133,14 +173,40 @@ SwapFree:        4096000 kB";
     fn test_get_bytes_converts_kb_to_bytes() {
         let mut map = HashMap::new();
         map.insert("MemTotal".to_string(), 1024);
-        let bytes = MemoryCollector::get_bytes(&map, "MemTotal").unwrap();
+        let bytes = MemoryCollector::get_bytes("/proc/meminfo", &map, "MemTotal").unwrap();
         assert_eq!(bytes, 1024 * 1024);
     }
 
+    #[test]
+    fn test_optional_fields() {
+        let content = format!(
+            "{SAMPLE_MEMINFO}\nSReclaimable:     300000 kB\nHugePages_Total:     512\nHugepagesize:       2048 kB"
+        );
+        let map = MemoryCollector::parse_meminfo(&content).unwrap();
+        assert_eq!(
+            MemoryCollector::optional_bytes(&map, "Cached"),
+            Some(2048000 * 1024)
+        );
+        assert_eq!(
+            MemoryCollector::optional_bytes(&map, "SReclaimable"),
+            Some(300000 * 1024)
+        );
+        // HugePages_* counts are taken as-is
+        assert_eq!(map.get("HugePages_Total").copied(), Some(512));
+        // fields missing on older kernels are None rather than an error
+        assert_eq!(MemoryCollector::optional_bytes(&map, "Shmem"), None);
+    }
+
     #[test]
     fn test_get_bytes_missing_field() {
         let map = HashMap::new();
-        assert!(MemoryCollector::get_bytes(&map, "NonExistent").is_err());
+        match MemoryCollector::get_bytes("/host/proc/meminfo", &map, "NonExistent") {
+            Err(CollectorError::ParseError { path, field, .. }) => {
+                assert_eq!(path, "/host/proc/meminfo");
+                assert_eq!(field, "NonExistent");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
     }
 
     #[test]
@@ -151,4 +217,61 @@ SwapFree:        4096000 kB";
         let pct = used as f64 / total as f64 * 100.0;
         assert!((pct - 75.0).abs() < 0.01);
     }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("meminfo"),
+            format!(
+                "{SAMPLE_MEMINFO}
+Dirty:              1024 kB
+Writeback:             0 kB
+AnonPages:       6144000 kB
+Mapped:           256000 kB
+Shmem:             64000 kB
+SReclaimable:     300000 kB
+SUnreclaim:       100000 kB
+CommitLimit:    16384000 kB
+Committed_AS:   12000000 kB
+HugePages_Total:     512
+HugePages_Free:      128
+HugePages_Rsvd:       16
+HugePages_Surp:        0
+Hugepagesize:       2048 kB
+"
+            ),
+        )
+        .unwrap();
+
+        let result = MemoryCollector::new(dir.path()).collect().await.unwrap();
+        // 75% used, swap half used
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("cached=2000 MB anon=6000 MB"));
+        match result.payload {
+            MetricPayload::Memory(s) => {
+                assert_eq!(s.total_bytes, 16384000 * 1024);
+                assert_eq!(s.used_bytes, (16384000 - 4096000) * 1024);
+                assert_eq!(s.swap_used_bytes, 4096000 * 1024);
+                assert_eq!(s.cached_bytes, Some(2048000 * 1024));
+                assert_eq!(s.buffers_bytes, Some(512000 * 1024));
+                assert_eq!(s.dirty_bytes, Some(1024 * 1024));
+                assert_eq!(s.writeback_bytes, Some(0));
+                assert_eq!(s.shmem_bytes, Some(64000 * 1024));
+                assert_eq!(s.slab_reclaimable_bytes, Some(300000 * 1024));
+                assert_eq!(s.slab_unreclaimable_bytes, Some(100000 * 1024));
+                assert_eq!(s.committed_as_bytes, Some(12000000 * 1024));
+                assert_eq!(s.commit_limit_bytes, Some(16384000 * 1024));
+                assert_eq!(s.anon_pages_bytes, Some(6144000 * 1024));
+                assert_eq!(s.mapped_bytes, Some(256000 * 1024));
+                // hugepage counts are pages, not kB
+                assert_eq!(s.huge_pages_total, Some(512));
+                assert_eq!(s.huge_pages_free, Some(128));
+                assert_eq!(s.huge_pages_rsvd, Some(16));
+                assert_eq!(s.huge_pages_surp, Some(0));
+                assert_eq!(s.hugepage_size_bytes, Some(2048 * 1024));
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
 }
@@ -1,7 +1,25 @@
+mod cgroup;
+pub mod cgroup_cpu;
+pub mod cgroup_memory;
 pub mod cpu;
+pub mod cpufreq;
+pub mod interrupts;
+pub mod leak;
 pub mod memory;
+pub mod numa;
+pub mod oom;
+pub mod overcommit;
+pub mod process;
+pub mod psi;
+pub mod restart;
+pub mod schedstat;
+pub mod selection;
+pub mod slab;
+pub mod vmstat;
 
 use crate::errors::CollectorError;
+use crate::maintenance::MaintenanceWindow;
+use crate::selectors::SelectorMatch;
 use async_trait::async_trait;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -16,6 +34,16 @@ pub trait Collector: Send + Sync {
     async fn collect(&mut self) -> Result<CollectionResult, CollectorError>;
 }
 
+/// run a collector and fill in `latency_us` with the wall time it took.
+pub async fn timed_collect(
+    collector: &mut dyn Collector,
+) -> Result<CollectionResult, CollectorError> {
+    let start = Instant::now();
+    let mut result = collector.collect().await?;
+    result.latency_us = start.elapsed().as_micros() as u64;
+    Ok(result)
+}
+
 /// result from any collector.
 #[derive(Debug, Clone, Serialize)]
 pub struct CollectionResult {
@@ -24,14 +52,47 @@ pub struct CollectionResult {
     pub message: String,
     pub metadata: HashMap<String, String>,
     pub latency_us: u64,
+    pub in_maintenance: bool,
     pub payload: MetricPayload,
 }
 
+impl CollectionResult {
+    /// flag the result as collected inside a maintenance window.
+    pub fn mark_maintenance(&mut self, window: &MaintenanceWindow) {
+        self.in_maintenance = true;
+        self.metadata
+            .insert("maintenance_source".into(), window.source.clone());
+        self.metadata
+            .insert("maintenance_ends_at".into(), window.ends_at.to_rfc3339());
+    }
+
+    /// whether the status should drive alerts and other actions.
+    /// Results collected during maintenance never do.
+    pub fn is_actionable(&self) -> bool {
+        !self.in_maintenance && self.status != CheckStatus::Healthy
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type")]
 pub enum MetricPayload {
-    Cpu(CpuSnapshot),
+    Cpu(Box<CpuSnapshot>),
     Memory(MemorySnapshot),
+    ProcessSelection(ProcessSelectionSnapshot),
+    CpuFreq(CpuFreqSnapshot),
+    Psi(PsiSnapshot),
+    CgroupCpu(CgroupCpuSnapshot),
+    Interrupts(InterruptsSnapshot),
+    Schedstat(SchedstatSnapshot),
+    Vmstat(VmstatSnapshot),
+    Oom(OomSnapshot),
+    CgroupMemory(CgroupMemorySnapshot),
+    Numa(NumaSnapshot),
+    MemoryLeak(MemoryLeakSnapshot),
+    Overcommit(OvercommitSnapshot),
+    Slab(SlabSnapshot),
+    Process(ProcessSnapshot),
+    Restarts(RestartSnapshot),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -44,17 +105,71 @@ pub enum CheckStatus {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct CpuSnapshot {
+    /// user + nice, excluding time spent running guests.
     pub user_pct: f64,
     pub system_pct: f64,
     pub iowait_pct: f64,
     pub idle_pct: f64,
+    /// time the hypervisor ran something else while we were runnable.
+    pub steal_pct: f64,
+    /// time spent running guest vCPUs (guest + guest_nice); the kernel
+    /// counts it in user time, here it is split out of `user_pct`.
+    pub guest_pct: f64,
     pub num_cores: u32,
     pub load_avg_1m: f64,
     pub load_avg_5m: f64,
     pub load_avg_15m: f64,
+    pub load_per_core_1m: f64,
+    pub load_per_core_5m: f64,
+    pub load_per_core_15m: f64,
+    pub load_trend: LoadTrend,
+    /// runnable / existing scheduling entities from /proc/loadavg.
+    pub tasks_running: u64,
+    pub tasks_total: u64,
+    pub per_core: Vec<CoreSnapshot>,
+    /// cores saturated while the aggregate looks idle.
+    pub hot_cores: Vec<u32>,
+    pub activity: KernelActivitySnapshot,
+    /// wall-clock length of the interval the percentages cover.
+    pub sample_window_ms: u64,
+    /// set when the interval was thrown away (first sample, hotplug, counter
+    /// reset, suspend); percentages are then zero and the next one re-baselines.
+    pub discarded_reason: Option<String>,
+}
+
+/// Kernel activity from /proc/stat. Rates are `None` until two samples exist.
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelActivitySnapshot {
+    pub context_switches_per_sec: Option<f64>,
+    pub interrupts_per_sec: Option<f64>,
+    pub forks_per_sec: Option<f64>,
+    pub procs_running: u64,
+    /// tasks in uninterruptible (usually I/O) sleep.
+    pub procs_blocked: u64,
+    /// boot time, seconds since the epoch.
+    pub boot_time: u64,
+}
+
+/// direction of the load average, 1m relative to 15m.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LoadTrend {
+    Rising,
+    Steady,
+    Falling,
 }
 
 #[derive(Debug, Clone, Serialize)]
+pub struct CoreSnapshot {
+    pub core: u32,
+    pub user_pct: f64,
+    pub system_pct: f64,
+    pub iowait_pct: f64,
+    pub idle_pct: f64,
+    pub steal_pct: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MemorySnapshot {
     pub total_bytes: u64,
     pub available_bytes: u64,
@@ -62,4 +177,374 @@ pub struct MemorySnapshot {
     pub swap_total_bytes: u64,
     pub swap_used_bytes: u64,
     pub memory_pressure_pct: f64,
+    // Breakdown fields are None when the running kernel does not report them.
+    pub cached_bytes: Option<u64>,
+    pub buffers_bytes: Option<u64>,
+    pub dirty_bytes: Option<u64>,
+    pub writeback_bytes: Option<u64>,
+    pub shmem_bytes: Option<u64>,
+    pub slab_reclaimable_bytes: Option<u64>,
+    pub slab_unreclaimable_bytes: Option<u64>,
+    pub committed_as_bytes: Option<u64>,
+    pub commit_limit_bytes: Option<u64>,
+    pub anon_pages_bytes: Option<u64>,
+    pub mapped_bytes: Option<u64>,
+    /// HugePages_* are page counts, not sizes; see `hugepage_size_bytes`.
+    pub huge_pages_total: Option<u64>,
+    pub huge_pages_free: Option<u64>,
+    pub huge_pages_rsvd: Option<u64>,
+    pub huge_pages_surp: Option<u64>,
+    pub hugepage_size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessSelectionSnapshot {
+    pub selections: Vec<SelectorMatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuFreqSnapshot {
+    /// false on hosts (typically VMs) that expose no cpufreq interface.
+    pub cpufreq_available: bool,
+    pub cores: Vec<CoreFreq>,
+    /// core + package thermal throttle events since the previous collection.
+    pub throttle_events: u64,
+    /// cores running a power-saving governor.
+    pub power_saving_cores: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreFreq {
+    pub core: u32,
+    pub cur_khz: Option<u64>,
+    pub min_khz: Option<u64>,
+    pub max_khz: Option<u64>,
+    pub governor: Option<String>,
+    /// scaling_driver, e.g. intel_pstate or acpi-cpufreq.
+    pub driver: Option<String>,
+    pub core_throttle_count: Option<u64>,
+    pub package_throttle_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PsiSnapshot {
+    /// false when the kernel has no PSI (pre-4.20, CONFIG_PSI=n or psi=0).
+    pub supported: bool,
+    pub cpu: Option<PsiResource>,
+    pub memory: Option<PsiResource>,
+    pub io: Option<PsiResource>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PsiResource {
+    /// share of time at least one task was stalled on the resource.
+    pub some: PsiLine,
+    /// share of time all non-idle tasks were stalled; absent for cpu on older kernels.
+    pub full: Option<PsiLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PsiLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// cumulative stall time in microseconds.
+    pub total_us: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CgroupCpuSnapshot {
+    /// false on cgroup v1 hosts or when the cpu controller is not enabled.
+    pub available: bool,
+    /// The configured cgroup; None for the agent's own one.
+    pub cgroup: Option<String>,
+    /// cpu.max quota in cores; None when unlimited.
+    pub quota_cores: Option<f64>,
+    /// CPUs consumed over the last interval; None on the first sample.
+    pub usage_cores: Option<f64>,
+    pub usage_pct_of_quota: Option<f64>,
+    /// Share of enforcement periods throttled over the last interval.
+    pub throttled_periods_pct: Option<f64>,
+    pub throttled_usec_delta: Option<u64>,
+    pub nr_periods: u64,
+    pub nr_throttled: u64,
+    pub throttled_usec: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InterruptsSnapshot {
+    /// Online CPU ids; `per_cpu_per_sec` columns follow this order.
+    pub cpus: Vec<u32>,
+    /// Hardware interrupts grouped by device, busiest first.
+    pub devices: Vec<InterruptRate>,
+    pub softirqs: Vec<InterruptRate>,
+    /// Devices and softirqs with most of their load on a single CPU.
+    pub imbalanced: Vec<String>,
+    /// Set when no rates could be computed for this interval.
+    pub discarded_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterruptRate {
+    pub name: String,
+    pub total_per_sec: f64,
+    pub per_cpu_per_sec: Vec<f64>,
+    pub top_cpu: u32,
+    pub top_cpu_share_pct: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SchedstatSnapshot {
+    /// false when the kernel was built without CONFIG_SCHEDSTATS.
+    pub supported: bool,
+    /// Run-queue wait per timeslice across all CPUs over the last interval.
+    pub avg_wait_per_timeslice_ms: Option<f64>,
+    pub worst_cpu: Option<u32>,
+    pub per_cpu: Vec<RunQueueLatency>,
+    /// Set when no latency could be computed for this interval.
+    pub discarded_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunQueueLatency {
+    pub cpu: u32,
+    /// None when the CPU ran no timeslices in the interval.
+    pub avg_wait_per_timeslice_ms: Option<f64>,
+    /// Milliseconds spent by tasks waiting on this CPU's run queue per second.
+    pub wait_ms_per_sec: f64,
+    pub timeslices_per_sec: f64,
+}
+
+/// Paging activity over the last interval, as per-second rates (pages for
+/// pswp*/pgscan*/pgsteal*, events otherwise).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VmstatSnapshot {
+    pub pswpin_per_sec: f64,
+    pub pswpout_per_sec: f64,
+    pub pgmajfault_per_sec: f64,
+    pub pgscan_kswapd_per_sec: f64,
+    pub pgscan_direct_per_sec: f64,
+    pub pgsteal_kswapd_per_sec: f64,
+    pub pgsteal_direct_per_sec: f64,
+    pub allocstall_per_sec: f64,
+    /// Pages reclaimed per page scanned; None when nothing was scanned.
+    pub reclaim_efficiency_pct: Option<f64>,
+    /// Set when the interval was not usable; rates are then zero.
+    pub discarded_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OomSnapshot {
+    /// false on kernels without the vmstat oom_kill counter (pre-4.13).
+    pub supported: bool,
+    pub oom_kill_total: u64,
+    pub kills_since_last: u64,
+    /// Victims identified from the kernel log; may be fewer than
+    /// `kills_since_last` if the log was unreadable or had wrapped, and may
+    /// include kills counted in an earlier cycle whose record was logged late.
+    pub victims: Vec<OomVictim>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OomVictim {
+    pub pid: u32,
+    pub name: String,
+    /// Microseconds since boot when the kill was logged.
+    pub uptime_us: u64,
+    pub total_vm_kb: Option<u64>,
+    pub anon_rss_kb: Option<u64>,
+    pub file_rss_kb: Option<u64>,
+    pub shmem_rss_kb: Option<u64>,
+    pub uid: Option<u32>,
+    pub oom_score_adj: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CgroupMemorySnapshot {
+    /// false on cgroup v1 hosts or when the memory controller is not enabled.
+    pub available: bool,
+    /// The configured cgroup; None for the agent's own one.
+    pub cgroup: Option<String>,
+    pub current_bytes: u64,
+    /// memory.current minus inactive page cache.
+    pub working_set_bytes: u64,
+    /// memory.max / memory.high; None when unlimited.
+    pub max_bytes: Option<u64>,
+    pub high_bytes: Option<u64>,
+    /// Working set against the lower of memory.max and memory.high.
+    pub working_set_pct_of_limit: Option<f64>,
+    pub anon_bytes: Option<u64>,
+    pub file_bytes: Option<u64>,
+    pub shmem_bytes: Option<u64>,
+    pub slab_bytes: Option<u64>,
+    pub sock_bytes: Option<u64>,
+    pub file_dirty_bytes: Option<u64>,
+    /// Cumulative memory.events counters.
+    pub events: CgroupMemoryEvents,
+    /// Events since the previous collection; None on the first sample.
+    pub events_since_last: Option<CgroupMemoryEvents>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CgroupMemoryEvents {
+    pub low: u64,
+    pub high: u64,
+    pub max: u64,
+    pub oom: u64,
+    pub oom_kill: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NumaSnapshot {
+    /// false when the kernel has no NUMA support (no /sys/devices/system/node).
+    pub supported: bool,
+    pub nodes: Vec<NumaNode>,
+    /// Node low on free memory while another node has plenty.
+    pub imbalanced_node: Option<u32>,
+    /// Nodes short on memory: many allocations meant for them had to be
+    /// served by another node (numa_foreign).
+    pub short_on_memory_nodes: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NumaNode {
+    pub node: u32,
+    pub mem_total_bytes: u64,
+    pub mem_free_bytes: u64,
+    pub free_pct: f64,
+    pub file_pages_bytes: Option<u64>,
+    pub anon_pages_bytes: Option<u64>,
+    /// numastat rates over the last interval; None on the first sample.
+    pub numa_hit_per_sec: Option<f64>,
+    /// Allocations meant for another node that landed here.
+    pub numa_miss_per_sec: Option<f64>,
+    /// Allocations meant for this node that landed elsewhere.
+    pub numa_foreign_per_sec: Option<f64>,
+    pub other_node_per_sec: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryLeakSnapshot {
+    pub mem_available_bytes: Option<u64>,
+    pub processes: Vec<ProcessMemoryTrend>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessMemoryTrend {
+    pub pid: u32,
+    pub name: String,
+    pub rss_bytes: u64,
+    pub pss_bytes: Option<u64>,
+    /// Samples in the rolling window and the time they span.
+    pub samples: usize,
+    pub window_secs: u64,
+    /// "pss" when every sample in the window has PSS, otherwise "rss".
+    pub trend_source: String,
+    /// Slope of the fitted trend line; None with too few samples.
+    pub growth_bytes_per_hour: Option<f64>,
+    pub r_squared: Option<f64>,
+    pub leaking: bool,
+    /// Time until MemAvailable is used up at the current growth rate.
+    pub exhaustion_eta_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OvercommitSnapshot {
+    /// vm.overcommit_memory: 0 heuristic, 1 always, 2 never.
+    pub overcommit_memory: u64,
+    pub policy: String,
+    pub overcommit_ratio_pct: Option<u64>,
+    /// vm.overcommit_kbytes, only when set; it then replaces the ratio.
+    pub overcommit_kbytes: Option<u64>,
+    pub committed_as_bytes: Option<u64>,
+    pub commit_limit_bytes: Option<u64>,
+    /// CommitLimit under policy 2, RAM plus swap otherwise.
+    pub limit_bytes: u64,
+    pub headroom_bytes: u64,
+    pub headroom_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlabSnapshot {
+    /// "slabinfo", or "meminfo" when /proc/slabinfo is not readable (it is
+    /// root-only) and only the reclaimable/unreclaimable totals are known.
+    pub source: String,
+    pub total_bytes: Option<u64>,
+    pub reclaimable_bytes: Option<u64>,
+    pub unreclaimable_bytes: Option<u64>,
+    /// Largest caches first, plus any growing cache outside the top.
+    pub top_caches: Vec<SlabCache>,
+    pub growing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlabCache {
+    pub name: String,
+    /// num_objs * objsize; excludes per-slab overhead.
+    pub bytes: u64,
+    pub active_objs: Option<u64>,
+    pub num_objs: Option<u64>,
+    pub obj_size: Option<u64>,
+    pub growth_bytes_per_hour: Option<f64>,
+    pub growing: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessSnapshot {
+    pub processes: Vec<ProcessStats>,
+    /// Selected PIDs that exited while being read.
+    pub vanished: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessStats {
+    pub pid: u32,
+    pub name: String,
+    /// Single-letter state from /proc/<pid>/stat (R, S, D, Z, ...).
+    pub state: String,
+    /// Share of one CPU; None on the first sample of a process.
+    pub cpu_pct: Option<f64>,
+    pub rss_bytes: u64,
+    pub swap_bytes: Option<u64>,
+    pub threads: u64,
+    pub voluntary_ctxt_switches: Option<u64>,
+    pub nonvoluntary_ctxt_switches: Option<u64>,
+    /// Storage I/O; None on the first sample or without access to /proc/<pid>/io.
+    pub read_bytes_per_sec: Option<f64>,
+    pub write_bytes_per_sec: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestartSnapshot {
+    pub selectors: Vec<SelectorRestarts>,
+    /// Restarts noticed during this collection.
+    pub events: Vec<RestartEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorRestarts {
+    pub selector: String,
+    pub instances: Vec<ProcessInstance>,
+    pub restarts_in_window: usize,
+    pub crash_loop: bool,
+}
+
+/// One process instance: a PID is only unique together with its start time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessInstance {
+    pub pid: u32,
+    /// Clock ticks after boot, field 22 of /proc/<pid>/stat.
+    pub starttime: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestartEvent {
+    pub selector: String,
+    /// None when the selector matched nothing in the previous cycle.
+    pub old_pid: Option<u32>,
+    pub new_pid: u32,
+    /// Time between the last sighting of the old instance and the start of
+    /// the new one; an upper bound on the real downtime, since the old
+    /// instance may have kept running for a while after it was last seen.
+    pub downtime_secs: Option<f64>,
 }
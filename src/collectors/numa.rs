@@ -0,0 +1,422 @@
+use super::{CheckStatus, CollectionResult, Collector, MetricPayload, NumaNode, NumaSnapshot};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// A node is imbalanced when its free memory drops below this share...
+const NODE_LOW_FREE_PCT: f64 = 10.0;
+/// ...while another node has at least this many points more free.
+const NODE_FREE_SPREAD_PCT: f64 = 30.0;
+/// Share of the allocations meant for a node that had to fall back to
+/// another node because it was out of memory.
+const NUMA_FOREIGN_DEGRADED_PCT: f64 = 10.0;
+
+/// NUMA memory collector reading
+/// /sys/devices/system/node/node*/{meminfo,numastat}.
+pub struct NumaCollector {
+    sys_root: PathBuf,
+    prev: Option<(Instant, Vec<NodeSample>)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct NodeSample {
+    node: u32,
+    mem_total_kb: u64,
+    mem_free_kb: u64,
+    file_pages_kb: Option<u64>,
+    anon_pages_kb: Option<u64>,
+    numa_hit: u64,
+    numa_miss: u64,
+    numa_foreign: u64,
+    other_node: u64,
+}
+
+impl NumaCollector {
+    pub fn new(sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            sys_root: sys_root.into(),
+            prev: None,
+        }
+    }
+
+    /// Parse a node meminfo file: `Node <n> <Key>: <value> kB` per line.
+    fn parse_node_meminfo(content: &str) -> HashMap<String, u64> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace().skip(2);
+                let key = parts.next()?.trim_end_matches(':');
+                let value = parts.next()?.parse().ok()?;
+                Some((key.to_string(), value))
+            })
+            .collect()
+    }
+
+    fn parse_numastat(content: &str) -> HashMap<String, u64> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(' ')?;
+                Some((key.to_string(), value.trim().parse().ok()?))
+            })
+            .collect()
+    }
+
+    /// One sample per node, or None when the kernel has no NUMA support
+    /// (CONFIG_NUMA=n) and the node directory does not exist.
+    fn read_samples(sys_root: &Path) -> Result<Option<Vec<NodeSample>>, CollectorError> {
+        let node_dir = sys_root.join("devices/system/node");
+        let entries = match fs::read_dir(&node_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(CollectorError::ProcReadError {
+                    path: node_dir.display().to_string(),
+                    source: e,
+                })
+            }
+        };
+
+        let mut samples = Vec::new();
+        for entry in entries.filter_map(Result::ok) {
+            let Some(node) = entry
+                .file_name()
+                .to_str()
+                .and_then(|n| n.strip_prefix("node"))
+                .and_then(|n| n.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let meminfo_path = entry.path().join("meminfo");
+            let meminfo =
+                fs::read_to_string(&meminfo_path).map_err(|e| CollectorError::ProcReadError {
+                    path: meminfo_path.display().to_string(),
+                    source: e,
+                })?;
+            let meminfo = Self::parse_node_meminfo(&meminfo);
+            let numastat = fs::read_to_string(entry.path().join("numastat"))
+                .map(|c| Self::parse_numastat(&c))
+                .unwrap_or_default();
+
+            let required = |key: &str| {
+                meminfo
+                    .get(key)
+                    .copied()
+                    .ok_or_else(|| CollectorError::ParseError {
+                        path: meminfo_path.display().to_string(),
+                        field: key.into(),
+                        raw: "field not found".into(),
+                    })
+            };
+            let counter = |key: &str| numastat.get(key).copied().unwrap_or(0);
+            samples.push(NodeSample {
+                node,
+                mem_total_kb: required("MemTotal")?,
+                mem_free_kb: required("MemFree")?,
+                file_pages_kb: meminfo.get("FilePages").copied(),
+                anon_pages_kb: meminfo.get("AnonPages").copied(),
+                numa_hit: counter("numa_hit"),
+                numa_miss: counter("numa_miss"),
+                numa_foreign: counter("numa_foreign"),
+                other_node: counter("other_node"),
+            });
+        }
+        samples.sort_by_key(|s| s.node);
+        Ok(Some(samples))
+    }
+
+    fn nodes(
+        prev: Option<(&Instant, &[NodeSample])>,
+        samples: &[NodeSample],
+        now: Instant,
+    ) -> Vec<NumaNode> {
+        samples
+            .iter()
+            .map(|s| {
+                let rates = prev.and_then(|(prev_at, prev)| {
+                    let p = prev.iter().find(|p| p.node == s.node)?;
+                    let elapsed = now.duration_since(*prev_at).as_secs_f64();
+                    if elapsed <= 0.0 {
+                        return None;
+                    }
+                    // a counter going backwards means the node went offline
+                    let rate = |c: u64, p: u64| Some(c.checked_sub(p)? as f64 / elapsed);
+                    Some((
+                        rate(s.numa_hit, p.numa_hit)?,
+                        rate(s.numa_miss, p.numa_miss)?,
+                        rate(s.numa_foreign, p.numa_foreign)?,
+                        rate(s.other_node, p.other_node)?,
+                    ))
+                });
+                NumaNode {
+                    node: s.node,
+                    mem_total_bytes: s.mem_total_kb * 1024,
+                    mem_free_bytes: s.mem_free_kb * 1024,
+                    free_pct: if s.mem_total_kb > 0 {
+                        s.mem_free_kb as f64 / s.mem_total_kb as f64 * 100.0
+                    } else {
+                        0.0
+                    },
+                    file_pages_bytes: s.file_pages_kb.map(|kb| kb * 1024),
+                    anon_pages_bytes: s.anon_pages_kb.map(|kb| kb * 1024),
+                    numa_hit_per_sec: rates.map(|r| r.0),
+                    numa_miss_per_sec: rates.map(|r| r.1),
+                    numa_foreign_per_sec: rates.map(|r| r.2),
+                    other_node_per_sec: rates.map(|r| r.3),
+                }
+            })
+            .collect()
+    }
+
+    /// The node short on memory while another has plenty, if any.
+    /// Memoryless nodes (CPU-only or CXL/HBM nodes not yet onlined) have
+    /// nothing to balance and are left out.
+    fn imbalanced_node(nodes: &[NumaNode]) -> Option<u32> {
+        let with_memory = || nodes.iter().filter(|n| n.mem_total_bytes > 0);
+        let low = with_memory().min_by(|a, b| a.free_pct.total_cmp(&b.free_pct))?;
+        let high = with_memory().map(|n| n.free_pct).fold(0.0_f64, f64::max);
+        (low.free_pct < NODE_LOW_FREE_PCT && high - low.free_pct >= NODE_FREE_SPREAD_PCT)
+            .then_some(low.node)
+    }
+
+    /// Nodes that could not serve a large share of the allocations meant
+    /// for them. numa_foreign counts those on the exhausted node itself;
+    /// numa_miss counts them on the node that took the overflow.
+    fn short_on_memory_nodes(nodes: &[NumaNode]) -> Vec<u32> {
+        nodes
+            .iter()
+            .filter(|n| {
+                let (Some(hit), Some(foreign)) = (n.numa_hit_per_sec, n.numa_foreign_per_sec)
+                else {
+                    return false;
+                };
+                hit + foreign > 0.0
+                    && foreign / (hit + foreign) * 100.0 >= NUMA_FOREIGN_DEGRADED_PCT
+            })
+            .map(|n| n.node)
+            .collect()
+    }
+
+    fn evaluate(snapshot: &NumaSnapshot) -> CheckStatus {
+        if snapshot.imbalanced_node.is_some() || !snapshot.short_on_memory_nodes.is_empty() {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for NumaCollector {
+    fn name(&self) -> &'static str {
+        "numa"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let sys_root = self.sys_root.clone();
+        let samples = tokio::task::spawn_blocking(move || Self::read_samples(&sys_root))
+            .await
+            .map_err(|e| CollectorError::ProcReadError {
+                path: self.sys_root.display().to_string(),
+                source: std::io::Error::other(e),
+            })??;
+        let Some(samples) = samples else {
+            return Ok(CollectionResult {
+                check_name: self.name().to_string(),
+                status: CheckStatus::Healthy,
+                message: "NUMA not supported".to_string(),
+                metadata: HashMap::new(),
+                latency_us: 0,
+                in_maintenance: false,
+                payload: MetricPayload::Numa(NumaSnapshot::default()),
+            });
+        };
+        let now = Instant::now();
+
+        let nodes = Self::nodes(
+            self.prev.as_ref().map(|(at, prev)| (at, prev.as_slice())),
+            &samples,
+            now,
+        );
+        self.prev = Some((now, samples));
+
+        let snapshot = NumaSnapshot {
+            supported: true,
+            imbalanced_node: Self::imbalanced_node(&nodes),
+            short_on_memory_nodes: Self::short_on_memory_nodes(&nodes),
+            nodes,
+        };
+        let status = Self::evaluate(&snapshot);
+
+        let per_node: Vec<String> = snapshot
+            .nodes
+            .iter()
+            .map(|n| {
+                format!(
+                    "node{} free={:.1}% foreign={}",
+                    n.node,
+                    n.free_pct,
+                    n.numa_foreign_per_sec
+                        .map_or("n/a".to_string(), |r| format!("{r:.0}/s"))
+                )
+            })
+            .collect();
+        let mut message = per_node.join(", ");
+        if let Some(node) = snapshot.imbalanced_node {
+            message.push_str(&format!(" imbalanced: node{node} low on memory"));
+        }
+        for node in &snapshot.short_on_memory_nodes {
+            message.push_str(&format!(" node{node} allocations spilling to other nodes"));
+        }
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Numa(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_meminfo(sys_root: &Path, node: u32, total_kb: u64, free_kb: u64) {
+        let dir = sys_root.join(format!("devices/system/node/node{node}"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("meminfo"),
+            format!(
+                "Node {node} MemTotal:       {total_kb} kB\n\
+                 Node {node} MemFree:        {free_kb} kB\n\
+                 Node {node} FilePages:      1024 kB\n\
+                 Node {node} HugePages_Total:     0\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    fn write_numastat(sys_root: &Path, node: u32, hit: u64, miss: u64, foreign: u64) {
+        let dir = sys_root.join(format!("devices/system/node/node{node}"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("numastat"),
+            format!(
+                "numa_hit {hit}\nnuma_miss {miss}\nnuma_foreign {foreign}\n\
+                 interleave_hit 0\nlocal_node {hit}\nother_node {miss}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_read_samples_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        write_meminfo(dir.path(), 1, 2000, 500);
+        write_numastat(dir.path(), 1, 10, 1, 0);
+        write_meminfo(dir.path(), 0, 4000, 1000);
+        write_numastat(dir.path(), 0, 20, 2, 0);
+        // non-node entries are ignored
+        fs::write(dir.path().join("devices/system/node/online"), "0-1\n").unwrap();
+
+        let samples = NumaCollector::read_samples(dir.path()).unwrap().unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].node, 0);
+        assert_eq!(samples[0].mem_total_kb, 4000);
+        assert_eq!(samples[0].file_pages_kb, Some(1024));
+        assert_eq!(samples[0].anon_pages_kb, None);
+        assert_eq!(samples[1].numa_miss, 1);
+    }
+
+    #[tokio::test]
+    async fn test_node_imbalance_degrades() {
+        let dir = tempfile::tempdir().unwrap();
+        // node0 5% free, node1 60% free
+        write_meminfo(dir.path(), 0, 100_000, 5_000);
+        write_meminfo(dir.path(), 1, 100_000, 60_000);
+        let mut collector = NumaCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Degraded);
+        assert!(result.message.contains("node0 low on memory"));
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_node_is_reported_not_the_one_taking_overflow() {
+        let dir = tempfile::tempdir().unwrap();
+        for node in 0..2 {
+            write_meminfo(dir.path(), node, 100_000, 50_000);
+            write_numastat(dir.path(), node, 1000, 0, 0);
+        }
+        let mut collector = NumaCollector::new(dir.path());
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Healthy
+        );
+
+        // node1 is out of memory: 100 of its allocations were served by
+        // node0, which counts them as numa_miss, node1 as numa_foreign
+        write_numastat(dir.path(), 0, 1100, 100, 0);
+        write_numastat(dir.path(), 1, 1100, 0, 100);
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Degraded);
+        assert!(result.message.contains("node1 allocations spilling"));
+        match result.payload {
+            MetricPayload::Numa(s) => {
+                assert_eq!(s.short_on_memory_nodes, vec![1]);
+                assert!(s.nodes[0].numa_miss_per_sec.unwrap() > 0.0);
+                assert!(s.nodes[1].numa_foreign_per_sec.unwrap() > 0.0);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_node_is_never_imbalanced() {
+        let dir = tempfile::tempdir().unwrap();
+        write_meminfo(dir.path(), 0, 100_000, 1_000);
+        let mut collector = NumaCollector::new(dir.path());
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Healthy
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memoryless_node_is_not_imbalanced() {
+        let dir = tempfile::tempdir().unwrap();
+        write_meminfo(dir.path(), 0, 100_000, 60_000);
+        write_meminfo(dir.path(), 1, 0, 0);
+        let mut collector = NumaCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Numa(s) => {
+                assert_eq!(s.nodes.len(), 2);
+                assert_eq!(s.imbalanced_node, None);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kernel_without_numa_is_healthy() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("devices/system")).unwrap();
+        let mut collector = NumaCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert_eq!(result.message, "NUMA not supported");
+        match result.payload {
+            MetricPayload::Numa(s) => assert!(!s.supported),
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
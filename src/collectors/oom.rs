@@ -0,0 +1,377 @@
+use super::vmstat::VmstatCollector;
+use super::{CheckStatus, CollectionResult, Collector, MetricPayload, OomSnapshot, OomVictim};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use nix::fcntl::OFlag;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// /dev/kmsg returns one record per read and fails with EINVAL if the
+/// buffer is smaller than the record.
+const KMSG_RECORD_MAX: usize = 8192;
+/// The kernel bumps oom_kill before it logs the victim, so a kill whose
+/// record is not in the log yet is looked up again for this many cycles.
+const VICTIM_RETRY_CYCLES: u32 = 3;
+
+/// OOM-kill detection: tracks the `oom_kill` counter in /proc/vmstat and,
+/// when it moves, identifies the victims from the kernel log.
+pub struct OomCollector {
+    proc_root: PathBuf,
+    kmsg_path: PathBuf,
+    prev_kills: Option<u64>,
+    /// Sequence number of the last kernel log record already reported.
+    last_seq: Option<u64>,
+    /// Kills counted in earlier cycles whose victims were not logged yet,
+    /// and how many more cycles to look for them.
+    unresolved_kills: u64,
+    retries_left: u32,
+}
+
+impl OomCollector {
+    pub fn new(proc_root: impl Into<PathBuf>, kmsg_path: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            kmsg_path: kmsg_path.into(),
+            prev_kills: None,
+            last_seq: None,
+            unresolved_kills: 0,
+            retries_left: 0,
+        }
+    }
+
+    /// Parse one /dev/kmsg record, `<prio>,<seq>,<usec>,<flags>[,...];<message>`,
+    /// into its sequence number, timestamp and victim if it is an OOM kill.
+    fn parse_kmsg_record(record: &str) -> Option<(u64, OomVictim)> {
+        let (header, message) = record.split_once(';')?;
+        let mut header = header.split(',');
+        let seq = header.nth(1)?.parse().ok()?;
+        let uptime_us = header.next()?.parse().ok()?;
+        Some((seq, Self::parse_victim(message, uptime_us)?))
+    }
+
+    /// Parse the kill line, present in this form since 4.x:
+    /// `Out of memory: Killed process 1234 (mysqld) total-vm:9000kB,
+    /// anon-rss:8000kB, file-rss:0kB, shmem-rss:0kB, UID:27 pgtables:100kB oom_score_adj:0`.
+    /// Older kernels omit some fields.
+    fn parse_victim(message: &str, uptime_us: u64) -> Option<OomVictim> {
+        let (_, rest) = message.split_once("Killed process ")?;
+        let (pid, rest) = rest.split_once(' ')?;
+        let rest = rest.strip_prefix('(')?;
+        let close = rest.rfind(')')?;
+        let (name, fields) = (&rest[..close], &rest[close + 1..]);
+
+        let mut victim = OomVictim {
+            pid: pid.parse().ok()?,
+            name: name.to_string(),
+            uptime_us,
+            ..OomVictim::default()
+        };
+        for field in fields.split([' ', ',']).filter(|f| !f.is_empty()) {
+            let Some((key, value)) = field.split_once(':') else {
+                continue;
+            };
+            let kb = || value.trim_end_matches("kB").parse::<u64>().ok();
+            match key {
+                "total-vm" => victim.total_vm_kb = kb(),
+                "anon-rss" => victim.anon_rss_kb = kb(),
+                "file-rss" => victim.file_rss_kb = kb(),
+                "shmem-rss" => victim.shmem_rss_kb = kb(),
+                "UID" => victim.uid = value.parse().ok(),
+                "oom_score_adj" => victim.oom_score_adj = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(victim)
+    }
+
+    /// Read every OOM kill still in the kernel ring buffer. Reads are
+    /// non-blocking so the end of the buffer returns EAGAIN instead of
+    /// waiting for new records.
+    fn read_kmsg(path: &Path) -> std::io::Result<Vec<(u64, OomVictim)>> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(OFlag::O_NONBLOCK.bits())
+            .open(path)?;
+        let mut reader = BufReader::with_capacity(KMSG_RECORD_MAX, file);
+        let mut kills = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => kills.extend(Self::parse_kmsg_record(line.trim_end())),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // records were overwritten before we read them
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(kills)
+    }
+
+    /// Victims for `new_kills` kills: records after the last reported one,
+    /// or the most recent ones if the log has not been read before.
+    fn select_victims(
+        kills: Vec<(u64, OomVictim)>,
+        last_seq: Option<u64>,
+        new_kills: u64,
+    ) -> Vec<(u64, OomVictim)> {
+        let mut fresh: Vec<_> = kills
+            .into_iter()
+            .filter(|(seq, _)| last_seq.is_none_or(|last| *seq > last))
+            .collect();
+        let excess = fresh.len().saturating_sub(new_kills as usize);
+        fresh.drain(..excess);
+        fresh
+    }
+}
+
+#[async_trait]
+impl Collector for OomCollector {
+    fn name(&self) -> &'static str {
+        "oom"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let path = self.proc_root.join("vmstat");
+        let path_str = path.display().to_string();
+        let content =
+            fs::read_to_string(&path)
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: path_str.clone(),
+                    source: e,
+                })?;
+        // oom_kill was added to vmstat in 4.13
+        let total = VmstatCollector::parse_vmstat(&path_str, &content)?
+            .get("oom_kill")
+            .copied();
+
+        let mut snapshot = OomSnapshot {
+            supported: total.is_some(),
+            oom_kill_total: total.unwrap_or(0),
+            ..OomSnapshot::default()
+        };
+        let mut metadata = HashMap::new();
+        if let (Some(total), Some(prev)) = (total, self.prev_kills) {
+            snapshot.kills_since_last = total.saturating_sub(prev);
+        }
+        if snapshot.kills_since_last > 0 {
+            self.retries_left = VICTIM_RETRY_CYCLES;
+        }
+        let pending = self.unresolved_kills + snapshot.kills_since_last;
+        self.unresolved_kills = 0;
+        if pending > 0 {
+            let kmsg = self.kmsg_path.clone();
+            let kills = tokio::task::spawn_blocking(move || Self::read_kmsg(&kmsg))
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: self.kmsg_path.display().to_string(),
+                    source: std::io::Error::other(e),
+                })?;
+            // the kill is still reported without victim details if the log
+            // is unreadable (no CAP_SYSLOG, /dev/kmsg not mapped in)
+            match kills {
+                Ok(kills) => {
+                    let victims = Self::select_victims(kills, self.last_seq, pending);
+                    if let Some((seq, _)) = victims.last() {
+                        self.last_seq = Some(*seq);
+                    }
+                    snapshot.victims = victims.into_iter().map(|(_, v)| v).collect();
+                    if self.retries_left > 0 {
+                        self.retries_left -= 1;
+                        self.unresolved_kills = pending - snapshot.victims.len() as u64;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(path = %self.kmsg_path.display(), error = %e, "cannot read kernel log for OOM victims");
+                }
+            }
+        }
+        self.prev_kills = total;
+
+        // victims found late belong to a kill counted in an earlier cycle
+        let status = if snapshot.kills_since_last > 0 || !snapshot.victims.is_empty() {
+            metadata.insert("event".to_string(), "oom_kill".to_string());
+            CheckStatus::Unhealthy
+        } else {
+            CheckStatus::Healthy
+        };
+        let message = if !snapshot.supported {
+            "oom_kill counter not available on this kernel".to_string()
+        } else if status == CheckStatus::Healthy {
+            format!("no OOM kills (total={})", snapshot.oom_kill_total)
+        } else if snapshot.victims.is_empty() {
+            format!(
+                "{} OOM kill(s), victim unknown (not in the kernel log yet, or it is unavailable)",
+                snapshot.kills_since_last
+            )
+        } else {
+            let victims: Vec<String> = snapshot
+                .victims
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{} (pid {}, anon-rss {} kB)",
+                        v.name,
+                        v.pid,
+                        v.anon_rss_kb.unwrap_or(0)
+                    )
+                })
+                .collect();
+            format!("OOM-killed: {}", victims.join(", "))
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata,
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Oom(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KILL_RECORD: &str = "3,2210,123456789,-;Out of memory: Killed process 4242 (mysqld) total-vm:9000000kB, anon-rss:8000000kB, file-rss:1200kB, shmem-rss:0kB, UID:27 pgtables:16000kB oom_score_adj:0";
+
+    fn kmsg_fixture(dir: &Path, records: &[&str]) -> PathBuf {
+        let path = dir.join("kmsg");
+        std::fs::write(&path, records.join("\n") + "\n").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_kmsg_record() {
+        let (seq, victim) = OomCollector::parse_kmsg_record(KILL_RECORD).unwrap();
+        assert_eq!(seq, 2210);
+        assert_eq!(victim.pid, 4242);
+        assert_eq!(victim.name, "mysqld");
+        assert_eq!(victim.uptime_us, 123456789);
+        assert_eq!(victim.anon_rss_kb, Some(8000000));
+        assert_eq!(victim.file_rss_kb, Some(1200));
+        assert_eq!(victim.uid, Some(27));
+        assert_eq!(victim.oom_score_adj, Some(0));
+
+        assert!(OomCollector::parse_kmsg_record("6,1,2,-;eth0: link up").is_none());
+        // continuation lines carry no header
+        assert!(OomCollector::parse_kmsg_record(" SUBSYSTEM=net").is_none());
+    }
+
+    #[test]
+    fn test_parse_victim_with_parens_in_name() {
+        let v = OomCollector::parse_victim("Killed process 7 (a (b)) total-vm:10kB", 0).unwrap();
+        assert_eq!(v.name, "a (b)");
+        assert_eq!(v.total_vm_kb, Some(10));
+        assert_eq!(v.anon_rss_kb, None);
+    }
+
+    #[test]
+    fn test_select_victims() {
+        let kills = |seqs: &[u64]| -> Vec<(u64, OomVictim)> {
+            seqs.iter().map(|s| (*s, OomVictim::default())).collect()
+        };
+        let seqs = |v: Vec<(u64, OomVictim)>| v.into_iter().map(|(s, _)| s).collect::<Vec<_>>();
+        assert_eq!(
+            seqs(OomCollector::select_victims(kills(&[1, 5, 9]), None, 1)),
+            vec![9]
+        );
+        assert_eq!(
+            seqs(OomCollector::select_victims(kills(&[1, 5, 9]), Some(1), 5)),
+            vec![5, 9]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oom_kill_reported_with_victim() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("vmstat"), "pswpin 0\noom_kill 3\n").unwrap();
+        let kmsg = kmsg_fixture(
+            dir.path(),
+            &["6,2200,100,-;eth0: link up", " SUBSYSTEM=net", KILL_RECORD],
+        );
+        let mut collector = OomCollector::new(dir.path(), &kmsg);
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+
+        std::fs::write(dir.path().join("vmstat"), "pswpin 0\noom_kill 4\n").unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        assert_eq!(result.metadata["event"], "oom_kill");
+        assert!(result.message.contains("mysqld (pid 4242"));
+
+        // the same record is not reported twice
+        std::fs::write(dir.path().join("vmstat"), "pswpin 0\noom_kill 5\n").unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        assert!(result.message.contains("victim unknown"));
+    }
+
+    #[tokio::test]
+    async fn test_victim_logged_one_cycle_late() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("vmstat"), "oom_kill 0\n").unwrap();
+        let kmsg = kmsg_fixture(dir.path(), &["6,2200,100,-;eth0: link up"]);
+        let mut collector = OomCollector::new(dir.path(), &kmsg);
+        collector.collect().await.unwrap();
+
+        // the counter moved before the kernel logged the kill
+        std::fs::write(dir.path().join("vmstat"), "oom_kill 1\n").unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        assert!(result.message.contains("victim unknown"));
+
+        kmsg_fixture(dir.path(), &["6,2200,100,-;eth0: link up", KILL_RECORD]);
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        assert!(result.message.contains("mysqld (pid 4242"));
+        match result.payload {
+            MetricPayload::Oom(s) => {
+                assert_eq!(s.kills_since_last, 0);
+                assert_eq!(s.victims.len(), 1);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        // resolved, so it is not reported again
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_kernel_log() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("vmstat"), "oom_kill 0\n").unwrap();
+        let mut collector = OomCollector::new(dir.path(), dir.path().join("no-kmsg"));
+        collector.collect().await.unwrap();
+        std::fs::write(dir.path().join("vmstat"), "oom_kill 1\n").unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+        match result.payload {
+            MetricPayload::Oom(s) => {
+                assert_eq!(s.kills_since_last, 1);
+                assert!(s.victims.is_empty());
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kernel_without_oom_kill_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("vmstat"), "pswpin 0\n").unwrap();
+        let mut collector = OomCollector::new(dir.path(), dir.path().join("kmsg"));
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("not available"));
+    }
+}
@@ -0,0 +1,254 @@
+use super::memory::MemoryCollector;
+use super::{CheckStatus, CollectionResult, Collector, MetricPayload, OvercommitSnapshot};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Headroom as a share of the limit that applies under the active policy.
+const HEADROOM_DEGRADED_PCT: f64 = 10.0;
+const HEADROOM_UNHEALTHY_PCT: f64 = 2.0;
+
+/// Overcommit headroom check. Under `vm.overcommit_memory=2` allocations
+/// fail once Committed_AS reaches CommitLimit, however much memory is still
+/// available; under the other policies the limit is RAM plus swap.
+pub struct OvercommitCollector {
+    proc_root: PathBuf,
+}
+
+impl OvercommitCollector {
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+        }
+    }
+
+    async fn read(path: &Path) -> Result<String, CollectorError> {
+        fs::read_to_string(path)
+            .await
+            .map_err(|e| CollectorError::ProcReadError {
+                path: path.display().to_string(),
+                source: e,
+            })
+    }
+
+    /// Read a single-value sysctl, `Ok(None)` if the kernel does not have it
+    /// (overcommit_kbytes was added in 3.14).
+    async fn read_sysctl(path: &Path) -> Result<Option<u64>, CollectorError> {
+        let content = match fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(CollectorError::ProcReadError {
+                    path: path.display().to_string(),
+                    source: e,
+                })
+            }
+        };
+        content
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| CollectorError::ParseError {
+                path: path.display().to_string(),
+                field: "value".into(),
+                raw: content.trim().to_string(),
+            })
+    }
+
+    fn policy_name(mode: u64) -> &'static str {
+        match mode {
+            0 => "heuristic",
+            1 => "always",
+            2 => "never",
+            _ => "unknown",
+        }
+    }
+
+    /// Limit and headroom in bytes under the active policy, from meminfo in kB.
+    fn headroom(mode: u64, meminfo: &HashMap<String, u64>) -> Option<(u64, u64)> {
+        let get = |key: &str| meminfo.get(key).map(|kb| kb * 1024);
+        if mode == 2 {
+            let limit = get("CommitLimit")?;
+            Some((limit, limit.saturating_sub(get("Committed_AS")?)))
+        } else {
+            // allocations succeed while there is memory to back them, and
+            // the OOM killer steps in once there is not
+            let limit = get("MemTotal")? + get("SwapTotal").unwrap_or(0);
+            let free = get("MemAvailable")? + get("SwapFree").unwrap_or(0);
+            Some((limit, free.min(limit)))
+        }
+    }
+
+    fn evaluate(headroom_pct: f64) -> CheckStatus {
+        if headroom_pct < HEADROOM_UNHEALTHY_PCT {
+            CheckStatus::Unhealthy
+        } else if headroom_pct < HEADROOM_DEGRADED_PCT {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for OvercommitCollector {
+    fn name(&self) -> &'static str {
+        "overcommit"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let vm = self.proc_root.join("sys/vm");
+        let mode_path = vm.join("overcommit_memory");
+        let mode =
+            Self::read_sysctl(&mode_path)
+                .await?
+                .ok_or_else(|| CollectorError::ProcReadError {
+                    path: mode_path.display().to_string(),
+                    source: ErrorKind::NotFound.into(),
+                })?;
+        let ratio = Self::read_sysctl(&vm.join("overcommit_ratio")).await?;
+        let kbytes = Self::read_sysctl(&vm.join("overcommit_kbytes")).await?;
+
+        let meminfo_path = self.proc_root.join("meminfo");
+        let meminfo = MemoryCollector::parse_meminfo(&Self::read(&meminfo_path).await?)?;
+        let (limit_bytes, headroom_bytes) =
+            Self::headroom(mode, &meminfo).ok_or_else(|| CollectorError::ParseError {
+                path: meminfo_path.display().to_string(),
+                field: if mode == 2 {
+                    "CommitLimit/Committed_AS"
+                } else {
+                    "MemTotal/MemAvailable"
+                }
+                .into(),
+                raw: "field not found".into(),
+            })?;
+        let headroom_pct = if limit_bytes > 0 {
+            headroom_bytes as f64 / limit_bytes as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        let snapshot = OvercommitSnapshot {
+            overcommit_memory: mode,
+            policy: Self::policy_name(mode).to_string(),
+            // a non-zero overcommit_kbytes takes precedence over the ratio
+            overcommit_ratio_pct: ratio,
+            overcommit_kbytes: kbytes.filter(|kb| *kb > 0),
+            committed_as_bytes: meminfo.get("Committed_AS").map(|kb| kb * 1024),
+            commit_limit_bytes: meminfo.get("CommitLimit").map(|kb| kb * 1024),
+            limit_bytes,
+            headroom_bytes,
+            headroom_pct,
+        };
+
+        let status = Self::evaluate(headroom_pct);
+        let message = format!(
+            "overcommit_memory={} ({}) headroom={} MB of {} MB ({:.1}%)",
+            mode,
+            snapshot.policy,
+            headroom_bytes / (1024 * 1024),
+            limit_bytes / (1024 * 1024),
+            headroom_pct
+        );
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Overcommit(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_proc(proc_root: &Path, mode: u64, meminfo: &str) {
+        let vm = proc_root.join("sys/vm");
+        std::fs::create_dir_all(&vm).unwrap();
+        std::fs::write(vm.join("overcommit_memory"), format!("{mode}\n")).unwrap();
+        std::fs::write(vm.join("overcommit_ratio"), "50\n").unwrap();
+        std::fs::write(vm.join("overcommit_kbytes"), "0\n").unwrap();
+        std::fs::write(proc_root.join("meminfo"), meminfo).unwrap();
+    }
+
+    // 16 GiB RAM, 2 GiB swap, 12 GiB available
+    const MEMINFO: &str = "MemTotal:       16777216 kB
+MemAvailable:   12582912 kB
+SwapTotal:       2097152 kB
+SwapFree:        2097152 kB
+CommitLimit:    10485760 kB
+Committed_AS:    9961472 kB
+";
+
+    #[tokio::test]
+    async fn test_strict_policy_uses_commit_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        write_proc(dir.path(), 2, MEMINFO);
+        let result = OvercommitCollector::new(dir.path())
+            .collect()
+            .await
+            .unwrap();
+        // 95% of CommitLimit committed despite 12 GiB available
+        assert_eq!(result.status, CheckStatus::Degraded);
+        match result.payload {
+            MetricPayload::Overcommit(s) => {
+                assert_eq!(s.policy, "never");
+                assert_eq!(s.overcommit_ratio_pct, Some(50));
+                assert_eq!(s.overcommit_kbytes, None);
+                assert_eq!(s.limit_bytes, 10485760 * 1024);
+                assert_eq!(s.headroom_bytes, (10485760 - 9961472) * 1024);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_policy_uses_available_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        write_proc(dir.path(), 0, MEMINFO);
+        let result = OvercommitCollector::new(dir.path())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Overcommit(s) => {
+                assert_eq!(s.policy, "heuristic");
+                assert_eq!(s.limit_bytes, 18 * 1024 * 1024 * 1024);
+                assert_eq!(s.headroom_bytes, 14 * 1024 * 1024 * 1024);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_limit_exhausted() {
+        let meminfo = MEMINFO.replace("9961472", "10485000");
+        let dir = tempfile::tempdir().unwrap();
+        write_proc(dir.path(), 2, &meminfo);
+        let result = OvercommitCollector::new(dir.path())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(result.status, CheckStatus::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn test_missing_commit_fields_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write_proc(dir.path(), 2, "MemTotal: 1024 kB\nMemAvailable: 512 kB\n");
+        let err = OvercommitCollector::new(dir.path())
+            .collect()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CollectorError::ParseError { .. }));
+    }
+}
@@ -0,0 +1,394 @@
+use super::cpu::ticks_per_sec;
+use super::{
+    CheckStatus, CollectionResult, Collector, MetricPayload, ProcessSnapshot, ProcessStats,
+};
+use crate::errors::CollectorError;
+use crate::selectors::MonitoredProcesses;
+use async_trait::async_trait;
+use nix::errno::Errno;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Instant;
+
+/// Per-process collector for the monitored processes: CPU, memory, threads,
+/// context switches and I/O from /proc/<pid>/stat, status and io.
+pub struct ProcessCollector {
+    monitored: MonitoredProcesses,
+    /// Counters from the previous cycle, for the per-second rates.
+    prev: HashMap<u32, (Instant, ProcessCounters)>,
+}
+
+/// The fields of /proc/<pid>/stat the agent uses.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProcStat {
+    pub comm: String,
+    pub state: char,
+    pub ppid: u32,
+    /// Clock ticks in user and kernel mode.
+    pub utime: u64,
+    pub stime: u64,
+    pub num_threads: u64,
+    /// Clock ticks since boot at which the process started; together with
+    /// the PID it identifies one process instance.
+    pub starttime: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProcessCounters {
+    starttime: u64,
+    cpu_ticks: u64,
+    read_bytes: Option<u64>,
+    write_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ProcessReading {
+    pid: u32,
+    stat: ProcStat,
+    rss_bytes: u64,
+    swap_bytes: Option<u64>,
+    voluntary_ctxt_switches: Option<u64>,
+    nonvoluntary_ctxt_switches: Option<u64>,
+    read_bytes: Option<u64>,
+    write_bytes: Option<u64>,
+}
+
+impl ProcessCollector {
+    pub fn new(monitored: MonitoredProcesses) -> Self {
+        Self {
+            monitored,
+            prev: HashMap::new(),
+        }
+    }
+
+    /// Parse /proc/<pid>/stat. comm may contain spaces and parentheses, so
+    /// the remaining fields are counted from the last ')'.
+    pub(crate) fn parse_stat(path: &str, content: &str) -> Result<ProcStat, CollectorError> {
+        let parse_error = |field: &str| CollectorError::ParseError {
+            path: path.into(),
+            field: field.into(),
+            raw: content.trim_end().into(),
+        };
+        let (head, rest) = content
+            .rsplit_once(')')
+            .ok_or_else(|| parse_error("comm"))?;
+        let (_, comm) = head.split_once('(').ok_or_else(|| parse_error("comm"))?;
+        // fields[0] is field 3 (state) in proc(5) numbering
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let number = |field: usize, name: &str| -> Result<u64, CollectorError> {
+            fields
+                .get(field - 3)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(|| parse_error(name))
+        };
+        Ok(ProcStat {
+            comm: comm.to_string(),
+            state: fields
+                .first()
+                .and_then(|s| s.chars().next())
+                .ok_or_else(|| parse_error("state"))?,
+            ppid: fields
+                .get(1)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(|| parse_error("ppid"))?,
+            utime: number(14, "utime")?,
+            stime: number(15, "stime")?,
+            num_threads: number(20, "num_threads")?,
+            starttime: number(22, "starttime")?,
+        })
+    }
+
+    /// Read a file under /proc/<pid>. A process that exits between listing
+    /// and reading shows up as ENOENT, or ESRCH for files that need the
+    /// task, and becomes `ProcessVanished`.
+    fn read_pid_file(proc_root: &Path, pid: u32, file: &str) -> Result<String, CollectorError> {
+        let path = proc_root.join(pid.to_string()).join(file);
+        fs::read_to_string(&path).map_err(|e| {
+            if e.kind() == ErrorKind::NotFound || e.raw_os_error() == Some(Errno::ESRCH as i32) {
+                CollectorError::ProcessVanished { pid }
+            } else {
+                CollectorError::ProcReadError {
+                    path: path.display().to_string(),
+                    source: e,
+                }
+            }
+        })
+    }
+
+    /// First number of a `Key:\t<value> [kB]` line in /proc/<pid>/status.
+    fn status_field(status: &str, key: &str) -> Option<u64> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|rest| rest.split_whitespace().next()?.parse().ok())
+    }
+
+    /// read_bytes/write_bytes from /proc/<pid>/io: bytes that reached the
+    /// block layer, unlike rchar/wchar which include page cache hits.
+    fn parse_io(content: &str) -> (Option<u64>, Option<u64>) {
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                .and_then(|v| v.trim().parse().ok())
+        };
+        (field("read_bytes"), field("write_bytes"))
+    }
+
+    fn read_process(proc_root: &Path, pid: u32) -> Result<ProcessReading, CollectorError> {
+        let stat_path = proc_root.join(pid.to_string()).join("stat");
+        let stat = Self::read_pid_file(proc_root, pid, "stat")?;
+        let stat = Self::parse_stat(&stat_path.display().to_string(), &stat)?;
+        let status = Self::read_pid_file(proc_root, pid, "status")?;
+        // io needs ptrace access to the process; without it only the I/O
+        // rates are missing
+        let (read_bytes, write_bytes) = match Self::read_pid_file(proc_root, pid, "io") {
+            Ok(io) => Self::parse_io(&io),
+            Err(CollectorError::ProcReadError { source, .. })
+                if source.kind() == ErrorKind::PermissionDenied =>
+            {
+                (None, None)
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(ProcessReading {
+            pid,
+            stat,
+            // kernel threads have no VmRSS
+            rss_bytes: Self::status_field(&status, "VmRSS").unwrap_or(0) * 1024,
+            swap_bytes: Self::status_field(&status, "VmSwap").map(|kb| kb * 1024),
+            voluntary_ctxt_switches: Self::status_field(&status, "voluntary_ctxt_switches"),
+            nonvoluntary_ctxt_switches: Self::status_field(&status, "nonvoluntary_ctxt_switches"),
+            read_bytes,
+            write_bytes,
+        })
+    }
+
+    /// Readings for every selected PID, plus the PIDs that exited while
+    /// being read.
+    fn read_processes(
+        pids: BTreeSet<u32>,
+        proc_root: &Path,
+    ) -> Result<(Vec<ProcessReading>, Vec<u32>), CollectorError> {
+        let mut readings = Vec::new();
+        let mut vanished = Vec::new();
+        for pid in pids {
+            match Self::read_process(proc_root, pid) {
+                Ok(reading) => readings.push(reading),
+                Err(CollectorError::ProcessVanished { pid }) => vanished.push(pid),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((readings, vanished))
+    }
+
+    /// Stats for one reading; rates are None on the first sample of a
+    /// process instance.
+    fn stats(
+        reading: &ProcessReading,
+        prev: Option<&(Instant, ProcessCounters)>,
+        now: Instant,
+    ) -> ProcessStats {
+        let counters = Self::counters(reading);
+        let interval = prev
+            .filter(|(_, p)| p.starttime == counters.starttime)
+            .map(|(at, p)| (now.duration_since(*at).as_secs_f64(), p))
+            .filter(|(elapsed, _)| *elapsed > 0.0);
+        let rate = |current: Option<u64>, previous: Option<u64>, elapsed: f64| {
+            Some(current?.saturating_sub(previous?) as f64 / elapsed)
+        };
+        ProcessStats {
+            pid: reading.pid,
+            name: reading.stat.comm.clone(),
+            state: reading.stat.state.to_string(),
+            cpu_pct: interval.map(|(elapsed, p)| {
+                counters.cpu_ticks.saturating_sub(p.cpu_ticks) as f64 / ticks_per_sec() / elapsed
+                    * 100.0
+            }),
+            rss_bytes: reading.rss_bytes,
+            swap_bytes: reading.swap_bytes,
+            threads: reading.stat.num_threads,
+            voluntary_ctxt_switches: reading.voluntary_ctxt_switches,
+            nonvoluntary_ctxt_switches: reading.nonvoluntary_ctxt_switches,
+            read_bytes_per_sec: interval
+                .and_then(|(elapsed, p)| rate(counters.read_bytes, p.read_bytes, elapsed)),
+            write_bytes_per_sec: interval
+                .and_then(|(elapsed, p)| rate(counters.write_bytes, p.write_bytes, elapsed)),
+        }
+    }
+
+    fn counters(reading: &ProcessReading) -> ProcessCounters {
+        ProcessCounters {
+            starttime: reading.stat.starttime,
+            cpu_ticks: reading.stat.utime + reading.stat.stime,
+            read_bytes: reading.read_bytes,
+            write_bytes: reading.write_bytes,
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for ProcessCollector {
+    fn name(&self) -> &'static str {
+        "process"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let pids = self.monitored.pids();
+        let proc_root = self.monitored.proc_root().to_path_buf();
+        let (readings, vanished) =
+            tokio::task::spawn_blocking(move || Self::read_processes(pids, &proc_root))
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: self.monitored.proc_root().display().to_string(),
+                    source: std::io::Error::other(e),
+                })??;
+        let now = Instant::now();
+
+        let processes: Vec<ProcessStats> = readings
+            .iter()
+            .map(|r| Self::stats(r, self.prev.get(&r.pid), now))
+            .collect();
+        self.prev = readings
+            .iter()
+            .map(|r| (r.pid, (now, Self::counters(r))))
+            .collect();
+        for pid in &vanished {
+            tracing::debug!(pid, "process exited during collection");
+        }
+        let snapshot = ProcessSnapshot {
+            processes,
+            vanished,
+        };
+
+        let mut summary: Vec<String> = snapshot
+            .processes
+            .iter()
+            .map(|p| {
+                format!(
+                    "{}[{}] cpu={} rss={} MB threads={}",
+                    p.name,
+                    p.pid,
+                    p.cpu_pct
+                        .map_or("-".to_string(), |pct| format!("{pct:.1}%")),
+                    p.rss_bytes / (1024 * 1024),
+                    p.threads
+                )
+            })
+            .collect();
+        if !snapshot.vanished.is_empty() {
+            summary.push(format!("exited during collection: {:?}", snapshot.vanished));
+        }
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status: CheckStatus::Healthy,
+            message: summary.join(" "),
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Process(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selectors::parse_selector;
+    use std::time::Duration;
+
+    const STAT: &str =
+        "42 (my) sql d) S 1 42 42 0 -1 4194560 100 0 0 0 500 300 0 0 20 0 37 0 987654 123 456";
+
+    fn write_process(root: &Path, pid: u32, stat: &str) {
+        let p = root.join(pid.to_string());
+        fs::create_dir_all(&p).unwrap();
+        fs::write(p.join("comm"), "mysqld\n").unwrap();
+        fs::write(p.join("stat"), stat).unwrap();
+        fs::write(
+            p.join("status"),
+            "Name:\tmysqld\nVmRSS:\t  4096 kB\nVmSwap:\t   128 kB\nThreads:\t37\n\
+             voluntary_ctxt_switches:\t1500\nnonvoluntary_ctxt_switches:\t20\n",
+        )
+        .unwrap();
+        fs::write(
+            p.join("io"),
+            "rchar: 1000\nwchar: 2000\nread_bytes: 4096\nwrite_bytes: 8192\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_stat_with_odd_comm() {
+        let stat = ProcessCollector::parse_stat("/proc/42/stat", STAT).unwrap();
+        assert_eq!(stat.comm, "my) sql d");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.ppid, 1);
+        assert_eq!((stat.utime, stat.stime), (500, 300));
+        assert_eq!(stat.num_threads, 37);
+        assert_eq!(stat.starttime, 987654);
+        assert!(ProcessCollector::parse_stat("/proc/42/stat", "42 (x) S 1").is_err());
+    }
+
+    #[test]
+    fn test_vanished_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = ProcessCollector::read_process(dir.path(), 42).unwrap_err();
+        assert!(matches!(err, CollectorError::ProcessVanished { pid: 42 }));
+    }
+
+    #[test]
+    fn test_rates_between_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        write_process(dir.path(), 42, STAT);
+        let first = ProcessCollector::read_process(dir.path(), 42).unwrap();
+        let start = Instant::now();
+        let prev = (start, ProcessCollector::counters(&first));
+        assert_eq!(ProcessCollector::stats(&first, None, start).cpu_pct, None);
+
+        // +150 ticks and +1 MiB written over 2 seconds
+        let mut second = first.clone();
+        second.stat.utime += 100;
+        second.stat.stime += 50;
+        second.write_bytes = Some(8192 + 1024 * 1024);
+        let stats = ProcessCollector::stats(&second, Some(&prev), start + Duration::from_secs(2));
+        assert_eq!(stats.cpu_pct, Some(75.0));
+        assert_eq!(stats.write_bytes_per_sec, Some(512.0 * 1024.0));
+        assert_eq!(stats.read_bytes_per_sec, Some(0.0));
+
+        // a restarted process that reused the PID starts a new baseline
+        second.stat.starttime += 1;
+        let stats = ProcessCollector::stats(&second, Some(&prev), start + Duration::from_secs(2));
+        assert_eq!(stats.cpu_pct, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        write_process(dir.path(), 42, STAT);
+
+        let monitored =
+            MonitoredProcesses::new(vec![parse_selector("name:mysqld").unwrap()], dir.path());
+        monitored.refresh().await;
+        let mut collector = ProcessCollector::new(monitored);
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Process(s) => {
+                assert!(s.vanished.is_empty());
+                let p = &s.processes[0];
+                assert_eq!(p.pid, 42);
+                assert_eq!(p.rss_bytes, 4096 * 1024);
+                assert_eq!(p.swap_bytes, Some(128 * 1024));
+                assert_eq!(p.threads, 37);
+                assert_eq!(p.voluntary_ctxt_switches, Some(1500));
+                assert_eq!(p.nonvoluntary_ctxt_switches, Some(20));
+                assert_eq!(p.cpu_pct, None);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
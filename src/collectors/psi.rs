@@ -0,0 +1,266 @@
+use super::{
+    CheckStatus, CollectionResult, Collector, MetricPayload, PsiLine, PsiResource, PsiSnapshot,
+};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use nix::errno::Errno;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Default stall thresholds, shared by [`PsiThresholds::default`] and the CLI.
+pub const DEFAULT_SOME_DEGRADED_PCT: f64 = 20.0;
+pub const DEFAULT_SOME_UNHEALTHY_PCT: f64 = 50.0;
+pub const DEFAULT_FULL_DEGRADED_PCT: f64 = 5.0;
+pub const DEFAULT_FULL_UNHEALTHY_PCT: f64 = 20.0;
+
+/// Stall percentages (avg60) at which a resource is degraded or unhealthy.
+/// `some` = at least one task stalled, `full` = all non-idle tasks stalled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PsiThresholds {
+    pub some_degraded_pct: f64,
+    pub some_unhealthy_pct: f64,
+    pub full_degraded_pct: f64,
+    pub full_unhealthy_pct: f64,
+}
+
+impl Default for PsiThresholds {
+    fn default() -> Self {
+        Self {
+            some_degraded_pct: DEFAULT_SOME_DEGRADED_PCT,
+            some_unhealthy_pct: DEFAULT_SOME_UNHEALTHY_PCT,
+            full_degraded_pct: DEFAULT_FULL_DEGRADED_PCT,
+            full_unhealthy_pct: DEFAULT_FULL_UNHEALTHY_PCT,
+        }
+    }
+}
+
+/// Pressure Stall Information collector reading /proc/pressure/{cpu,memory,io}.
+pub struct PsiCollector {
+    proc_root: PathBuf,
+    thresholds: PsiThresholds,
+}
+
+impl PsiCollector {
+    pub fn new(proc_root: impl Into<PathBuf>, thresholds: PsiThresholds) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            thresholds,
+        }
+    }
+
+    /// Parse one pressure file:
+    /// `some avg10=0.12 avg60=0.05 avg300=0.01 total=123456` plus an optional `full` line.
+    fn parse_pressure(path: &str, content: &str) -> Result<PsiResource, CollectorError> {
+        let mut some = None;
+        let mut full = None;
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let slot = match parts.next() {
+                Some("some") => &mut some,
+                Some("full") => &mut full,
+                _ => continue,
+            };
+            let mut parsed = PsiLine {
+                avg10: 0.0,
+                avg60: 0.0,
+                avg300: 0.0,
+                total_us: 0,
+            };
+            for kv in parts {
+                let err = || CollectorError::ParseError {
+                    path: path.into(),
+                    field: kv.split('=').next().unwrap_or(kv).into(),
+                    raw: line.to_string(),
+                };
+                let (key, value) = kv.split_once('=').ok_or_else(err)?;
+                match key {
+                    "avg10" => parsed.avg10 = value.parse().map_err(|_| err())?,
+                    "avg60" => parsed.avg60 = value.parse().map_err(|_| err())?,
+                    "avg300" => parsed.avg300 = value.parse().map_err(|_| err())?,
+                    "total" => parsed.total_us = value.parse().map_err(|_| err())?,
+                    _ => {}
+                }
+            }
+            *slot = Some(parsed);
+        }
+
+        let some = some.ok_or_else(|| CollectorError::ParseError {
+            path: path.into(),
+            field: "some".into(),
+            raw: content.to_string(),
+        })?;
+        Ok(PsiResource { some, full })
+    }
+
+    fn evaluate(&self, snapshot: &PsiSnapshot) -> CheckStatus {
+        let t = &self.thresholds;
+        let some = [&snapshot.cpu, &snapshot.memory, &snapshot.io]
+            .iter()
+            .filter_map(|r| r.as_ref().map(|r| r.some.avg60))
+            .fold(0.0_f64, f64::max);
+        // system-wide cpu "full" is always zero and not meaningful
+        let full = [&snapshot.memory, &snapshot.io]
+            .iter()
+            .filter_map(|r| r.as_ref()?.full.as_ref().map(|f| f.avg60))
+            .fold(0.0_f64, f64::max);
+
+        if some > t.some_unhealthy_pct || full > t.full_unhealthy_pct {
+            CheckStatus::Unhealthy
+        } else if some > t.some_degraded_pct || full > t.full_degraded_pct {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+
+    /// Read one resource. `Ok(None)` when the kernel has no PSI: the
+    /// directory is missing (pre-4.20, CONFIG_PSI=n) or reads fail with
+    /// EOPNOTSUPP (booted with psi=0).
+    async fn read_resource(&self, resource: &str) -> Result<Option<PsiResource>, CollectorError> {
+        let path = self.proc_root.join("pressure").join(resource);
+        let path_str = path.display().to_string();
+        match fs::read_to_string(&path).await {
+            Ok(content) => Self::parse_pressure(&path_str, &content).map(Some),
+            Err(e)
+                if e.kind() == ErrorKind::NotFound
+                    || e.raw_os_error() == Some(Errno::EOPNOTSUPP as i32) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(CollectorError::ProcReadError {
+                path: path_str,
+                source: e,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for PsiCollector {
+    fn name(&self) -> &'static str {
+        "psi"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let snapshot = {
+            let cpu = self.read_resource("cpu").await?;
+            let memory = self.read_resource("memory").await?;
+            let io = self.read_resource("io").await?;
+            PsiSnapshot {
+                supported: cpu.is_some() || memory.is_some() || io.is_some(),
+                cpu,
+                memory,
+                io,
+            }
+        };
+
+        let status = self.evaluate(&snapshot);
+        let message = if !snapshot.supported {
+            "psi not supported by this kernel".to_string()
+        } else {
+            let fmt = |name: &str, r: &Option<PsiResource>| match r {
+                Some(r) => format!(
+                    "{}={:.1}%/{:.1}%",
+                    name,
+                    r.some.avg60,
+                    r.full.as_ref().map_or(0.0, |f| f.avg60)
+                ),
+                None => format!("{name}=n/a"),
+            };
+            format!(
+                "some/full avg60: {} {} {}",
+                fmt("cpu", &snapshot.cpu),
+                fmt("memory", &snapshot.memory),
+                fmt("io", &snapshot.io)
+            )
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Psi(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_MEMORY: &str = "\
+some avg10=12.50 avg60=8.25 avg300=2.00 total=98765432
+full avg10=4.00 avg60=3.10 avg300=0.50 total=12345678";
+
+    const SAMPLE_CPU_OLD_KERNEL: &str = "some avg10=0.00 avg60=0.15 avg300=0.10 total=4242";
+
+    #[test]
+    fn test_parse_pressure() {
+        let r = PsiCollector::parse_pressure("/proc/pressure/memory", SAMPLE_MEMORY).unwrap();
+        assert!((r.some.avg10 - 12.5).abs() < f64::EPSILON);
+        assert!((r.some.avg60 - 8.25).abs() < f64::EPSILON);
+        assert_eq!(r.some.total_us, 98765432);
+        let full = r.full.unwrap();
+        assert!((full.avg300 - 0.5).abs() < f64::EPSILON);
+        assert_eq!(full.total_us, 12345678);
+    }
+
+    #[test]
+    fn test_parse_pressure_without_full_line() {
+        let r = PsiCollector::parse_pressure("/proc/pressure/cpu", SAMPLE_CPU_OLD_KERNEL).unwrap();
+        assert!(r.full.is_none());
+        assert_eq!(r.some.total_us, 4242);
+    }
+
+    #[test]
+    fn test_parse_pressure_rejects_garbage() {
+        assert!(PsiCollector::parse_pressure("/proc/pressure/io", "").is_err());
+        assert!(PsiCollector::parse_pressure("/proc/pressure/io", "some avg10=x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let pressure = dir.path().join("pressure");
+        std::fs::create_dir_all(&pressure).unwrap();
+        std::fs::write(pressure.join("cpu"), SAMPLE_CPU_OLD_KERNEL).unwrap();
+        std::fs::write(pressure.join("memory"), SAMPLE_MEMORY).unwrap();
+        std::fs::write(
+            pressure.join("io"),
+            "some avg10=30.00 avg60=25.00 avg300=9.00 total=1\nfull avg10=2.00 avg60=1.00 avg300=0.20 total=1",
+        )
+        .unwrap();
+
+        let mut collector = PsiCollector::new(dir.path(), PsiThresholds::default());
+        let result = collector.collect().await.unwrap();
+        // io some avg60 = 25% > 20% default
+        assert_eq!(result.status, CheckStatus::Degraded);
+
+        let strict = PsiThresholds {
+            some_unhealthy_pct: 24.0,
+            ..PsiThresholds::default()
+        };
+        let mut collector = PsiCollector::new(dir.path(), strict);
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Unhealthy
+        );
+    }
+
+    #[tokio::test]
+    async fn test_kernel_without_psi() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut collector = PsiCollector::new(dir.path(), PsiThresholds::default());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Psi(s) => assert!(!s.supported),
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,551 @@
+use super::cpu::ticks_per_sec;
+use super::process::ProcessCollector;
+use super::{
+    CheckStatus, CollectionResult, Collector, MetricPayload, ProcessInstance, RestartEvent,
+    RestartSnapshot, SelectorRestarts,
+};
+use crate::errors::CollectorError;
+use crate::selectors::{MonitoredProcesses, SelectorMatch};
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Default crash-loop thresholds, shared by [`CrashLoopThresholds::default`] and the CLI.
+pub const DEFAULT_MAX_RESTARTS: usize = 3;
+pub const DEFAULT_WINDOW_MINS: u64 = 10;
+/// Longest restart-counting window accepted.
+pub const MAX_WINDOW_MINS: u64 = 24 * 60;
+
+/// More than `max_restarts` restarts within `window` is a crash loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrashLoopThresholds {
+    pub max_restarts: usize,
+    pub window: Duration,
+}
+
+impl Default for CrashLoopThresholds {
+    fn default() -> Self {
+        Self {
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            window: Duration::from_secs(DEFAULT_WINDOW_MINS * 60),
+        }
+    }
+}
+
+/// Restart and crash-loop detection. Tracks the identity (PID plus start
+/// time) of every primary process each selector matches, so a process that
+/// restarted between two cycles is noticed even though it is up on both.
+/// A primary is a matched process whose parent is not matched too; workers
+/// forked and reaped by it are not mistaken for restarts.
+pub struct RestartCollector {
+    monitored: MonitoredProcesses,
+    thresholds: CrashLoopThresholds,
+    state: HashMap<String, SelectorState>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SelectorState {
+    /// Every instance matched last time, primary or not.
+    instances: Vec<ProcessInstance>,
+    primaries: Vec<ProcessInstance>,
+    /// Primaries that ended without a replacement yet, with when they were
+    /// last seen running; a primary that starts later replaces one of them.
+    ended: VecDeque<(Instant, ProcessInstance)>,
+    /// When any matched process was last seen running.
+    last_seen: Option<Instant>,
+    restarts: VecDeque<Instant>,
+}
+
+/// The instances each selector matched during one pass.
+#[derive(Debug, Clone, PartialEq)]
+struct Observation {
+    selector: String,
+    instances: Vec<ProcessInstance>,
+    /// Instances whose parent is not among `instances`.
+    primaries: Vec<ProcessInstance>,
+}
+
+impl Observation {
+    /// Build from each matched instance and its parent PID.
+    fn new(selector: String, matched: Vec<(ProcessInstance, u32)>) -> Self {
+        let primaries = matched
+            .iter()
+            .filter(|(_, ppid)| !matched.iter().any(|(i, _)| i.pid == *ppid))
+            .map(|(i, _)| i.clone())
+            .collect();
+        Self {
+            selector,
+            instances: matched.into_iter().map(|(i, _)| i).collect(),
+            primaries,
+        }
+    }
+}
+
+impl RestartCollector {
+    pub fn new(monitored: MonitoredProcesses, thresholds: CrashLoopThresholds) -> Self {
+        Self {
+            monitored,
+            thresholds,
+            state: HashMap::new(),
+        }
+    }
+
+    /// Seconds since boot, the first field of /proc/uptime.
+    fn parse_uptime(content: &str) -> Option<f64> {
+        content.split_whitespace().next()?.parse().ok()
+    }
+
+    fn observe(matches: Vec<SelectorMatch>, proc_root: &Path) -> (Vec<Observation>, Option<f64>) {
+        let observations = matches
+            .into_iter()
+            .map(|m| {
+                // a process that exits while being read is simply not running
+                let matched = m
+                    .pids
+                    .into_iter()
+                    .filter_map(|pid| {
+                        let path = proc_root.join(pid.to_string()).join("stat");
+                        let content = fs::read_to_string(&path).ok()?;
+                        let stat =
+                            ProcessCollector::parse_stat(&path.display().to_string(), &content)
+                                .ok()?;
+                        let instance = ProcessInstance {
+                            pid,
+                            starttime: stat.starttime,
+                        };
+                        Some((instance, stat.ppid))
+                    })
+                    .collect();
+                Observation::new(m.selector, matched)
+            })
+            .collect();
+        let uptime = fs::read_to_string(proc_root.join("uptime"))
+            .ok()
+            .and_then(|content| Self::parse_uptime(&content));
+        (observations, uptime)
+    }
+
+    /// Compare an observation taken at `now` with the previous one for the
+    /// same selector and return the restarts it shows.
+    ///
+    /// Primaries that ended are set aside until a new primary starts and
+    /// replaces one of them; only processes that were not running before
+    /// count as started, so an orphaned worker left behind by its master is
+    /// not a restart. Unpaired starts count as restarts only after the
+    /// selector had matched nothing.
+    fn record(
+        &mut self,
+        now: Instant,
+        observation: &Observation,
+        uptime_secs: Option<f64>,
+    ) -> Vec<RestartEvent> {
+        let window = self.thresholds.window;
+        let state = self.state.entry(observation.selector.clone()).or_default();
+        let mut events = Vec::new();
+        if let Some(last_seen) = state.last_seen {
+            for old in &state.primaries {
+                if !observation.instances.contains(old) {
+                    state.ended.push_back((last_seen, old.clone()));
+                }
+            }
+            let was_down = state.instances.is_empty();
+            let started = observation
+                .primaries
+                .iter()
+                .filter(|i| !state.instances.contains(i));
+            for new in started {
+                let old = state.ended.pop_front();
+                if old.is_none() && !was_down {
+                    continue;
+                }
+                let old_seen = old.as_ref().map_or(last_seen, |(at, _)| *at);
+                // the new instance started `age` ago; the old one was still
+                // running at `old_seen`
+                let downtime_secs = uptime_secs.map(|uptime| {
+                    let age = uptime - new.starttime as f64 / ticks_per_sec();
+                    (now.duration_since(old_seen).as_secs_f64() - age).max(0.0)
+                });
+                events.push(RestartEvent {
+                    selector: observation.selector.clone(),
+                    old_pid: old.filter(|_| !was_down).map(|(_, i)| i.pid),
+                    new_pid: new.pid,
+                    downtime_secs,
+                });
+                state.restarts.push_back(now);
+            }
+        }
+        // a primary not replaced within the window was stopped, not restarted
+        while state
+            .ended
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            state.ended.pop_front();
+        }
+        while state
+            .restarts
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > window)
+        {
+            state.restarts.pop_front();
+        }
+        state.instances = observation.instances.clone();
+        state.primaries = observation.primaries.clone();
+        if !state.instances.is_empty() {
+            state.last_seen = Some(now);
+        }
+        events
+    }
+}
+
+#[async_trait]
+impl Collector for RestartCollector {
+    fn name(&self) -> &'static str {
+        "restarts"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let matches = self.monitored.matches();
+        let proc_root = self.monitored.proc_root().to_path_buf();
+        let (observations, uptime_secs) =
+            tokio::task::spawn_blocking(move || Self::observe(matches, &proc_root))
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: self.monitored.proc_root().display().to_string(),
+                    source: std::io::Error::other(e),
+                })?;
+        let now = Instant::now();
+
+        let mut snapshot = RestartSnapshot::default();
+        for observation in &observations {
+            snapshot
+                .events
+                .extend(self.record(now, observation, uptime_secs));
+            let restarts_in_window = self.state[&observation.selector].restarts.len();
+            snapshot.selectors.push(SelectorRestarts {
+                selector: observation.selector.clone(),
+                instances: observation.instances.clone(),
+                restarts_in_window,
+                crash_loop: restarts_in_window > self.thresholds.max_restarts,
+            });
+        }
+
+        let looping: Vec<String> = snapshot
+            .selectors
+            .iter()
+            .filter(|s| s.crash_loop)
+            .map(|s| {
+                format!(
+                    "{} restarted {} times in {}m",
+                    s.selector,
+                    s.restarts_in_window,
+                    self.thresholds.window.as_secs() / 60
+                )
+            })
+            .collect();
+        let restarted: Vec<String> = snapshot
+            .events
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} restarted (pid {} -> {}{})",
+                    e.selector,
+                    e.old_pid.map_or("-".to_string(), |pid| pid.to_string()),
+                    e.new_pid,
+                    e.downtime_secs
+                        .map(|d| format!(", down {d:.1}s"))
+                        .unwrap_or_default()
+                )
+            })
+            .collect();
+
+        let mut metadata = HashMap::new();
+        let (status, message) = if !looping.is_empty() {
+            metadata.insert("event".to_string(), "crash_loop".to_string());
+            (
+                CheckStatus::Unhealthy,
+                format!("crash loop: {}", looping.join("; ")),
+            )
+        } else if !restarted.is_empty() {
+            metadata.insert("event".to_string(), "restart".to_string());
+            (CheckStatus::Degraded, restarted.join("; "))
+        } else {
+            (
+                CheckStatus::Healthy,
+                format!(
+                    "no restarts across {} selector(s)",
+                    snapshot.selectors.len()
+                ),
+            )
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata,
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Restarts(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selectors::parse_selector;
+
+    /// Instances as (pid, ppid, starttime).
+    fn observation(instances: &[(u32, u32, u64)]) -> Observation {
+        Observation::new(
+            "name:mysqld".into(),
+            instances
+                .iter()
+                .map(|&(pid, ppid, starttime)| (ProcessInstance { pid, starttime }, ppid))
+                .collect(),
+        )
+    }
+
+    fn collector() -> RestartCollector {
+        RestartCollector::new(
+            MonitoredProcesses::new(Vec::new(), "/proc"),
+            CrashLoopThresholds::default(),
+        )
+    }
+
+    #[test]
+    fn test_restart_between_cycles() {
+        let mut c = collector();
+        let start = Instant::now();
+        assert!(c
+            .record(start, &observation(&[(100, 1, 1000)]), None)
+            .is_empty());
+        assert!(c
+            .record(
+                start + Duration::from_secs(10),
+                &observation(&[(100, 1, 1000)]),
+                None
+            )
+            .is_empty());
+
+        // new instance started 4 s before the cycle at uptime 100 s,
+        // 6 s after the old one was last seen
+        let events = c.record(
+            start + Duration::from_secs(20),
+            &observation(&[(230, 1, 9600)]),
+            Some(100.0),
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].old_pid, Some(100));
+        assert_eq!(events[0].new_pid, 230);
+        assert!((events[0].downtime_secs.unwrap() - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pid_reuse_is_a_restart() {
+        let mut c = collector();
+        let start = Instant::now();
+        c.record(start, &observation(&[(100, 1, 1000)]), None);
+        let events = c.record(
+            start + Duration::from_secs(10),
+            &observation(&[(100, 1, 2000)]),
+            None,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].old_pid, Some(100));
+    }
+
+    #[test]
+    fn test_return_after_downtime() {
+        let mut c = collector();
+        let start = Instant::now();
+        c.record(start, &observation(&[(100, 1, 1000)]), None);
+        assert!(c
+            .record(start + Duration::from_secs(10), &observation(&[]), None)
+            .is_empty());
+        let events = c.record(
+            start + Duration::from_secs(20),
+            &observation(&[(300, 1, 5000)]),
+            None,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].old_pid, None);
+    }
+
+    #[test]
+    fn test_extra_worker_is_not_a_restart() {
+        let mut c = collector();
+        let start = Instant::now();
+        c.record(start, &observation(&[(100, 1, 1000)]), None);
+        let events = c.record(
+            start + Duration::from_secs(10),
+            &observation(&[(100, 1, 1000), (101, 100, 1500)]),
+            None,
+        );
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_worker_churn_is_not_a_restart() {
+        let mut c = collector();
+        let start = Instant::now();
+        c.record(
+            start,
+            &observation(&[(100, 1, 1000), (101, 100, 1500)]),
+            None,
+        );
+        // the master reaped worker 101 and forked 102
+        let events = c.record(
+            start + Duration::from_secs(10),
+            &observation(&[(100, 1, 1000), (102, 100, 1600)]),
+            None,
+        );
+        assert!(events.is_empty());
+        assert!(c.state["name:mysqld"].restarts.is_empty());
+
+        // the master itself restarting still counts, once
+        let events = c.record(
+            start + Duration::from_secs(20),
+            &observation(&[(200, 1, 2000), (201, 200, 2001)]),
+            None,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].old_pid, Some(100));
+        assert_eq!(events[0].new_pid, 200);
+    }
+
+    #[test]
+    fn test_independent_instances_restart_separately() {
+        let mut c = collector();
+        let start = Instant::now();
+        // mysqld_multi: two servers, neither the parent of the other
+        c.record(
+            start,
+            &observation(&[(100, 50, 1000), (200, 50, 1100)]),
+            None,
+        );
+        let events = c.record(
+            start + Duration::from_secs(10),
+            &observation(&[(100, 50, 1000), (300, 50, 1900)]),
+            None,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].old_pid, Some(200));
+        assert_eq!(events[0].new_pid, 300);
+    }
+
+    #[test]
+    fn test_orphaned_worker_is_not_a_restart() {
+        let mut c = collector();
+        let start = Instant::now();
+        c.record(
+            start,
+            &observation(&[(100, 1, 1000), (101, 100, 1500)]),
+            None,
+        );
+        // the master died and its worker was reparented to init
+        assert!(c
+            .record(
+                start + Duration::from_secs(10),
+                &observation(&[(101, 1, 1500)]),
+                None
+            )
+            .is_empty());
+        // the orphan exits as well
+        assert!(c
+            .record(start + Duration::from_secs(20), &observation(&[]), None)
+            .is_empty());
+        // one restart, once a new master comes up
+        let events = c.record(
+            start + Duration::from_secs(30),
+            &observation(&[(300, 1, 5000)]),
+            None,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(c.state["name:mysqld"].restarts.len(), 1);
+    }
+
+    #[test]
+    fn test_restarts_expire_from_window() {
+        let mut c = collector();
+        let start = Instant::now();
+        for i in 0..5u64 {
+            c.record(
+                start + Duration::from_secs(i * 60),
+                &observation(&[(100 + i as u32, 1, 1000 + i)]),
+                None,
+            );
+        }
+        assert_eq!(c.state["name:mysqld"].restarts.len(), 4);
+        c.record(
+            start + Duration::from_secs(4 * 60 + 11 * 60),
+            &observation(&[(104, 1, 1004)]),
+            None,
+        );
+        assert!(c.state["name:mysqld"].restarts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_crash_loop_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("uptime"), "5000.00 19000.00\n").unwrap();
+        let write_process = |pid: u32, starttime: u64| {
+            let p = dir.path().join(pid.to_string());
+            std::fs::create_dir_all(&p).unwrap();
+            std::fs::write(p.join("comm"), "mysqld\n").unwrap();
+            std::fs::write(
+                p.join("stat"),
+                format!("{pid} (mysqld) S 1 {pid} {pid} 0 -1 4194560 100 0 0 0 5 3 0 0 20 0 30 0 {starttime} 123 456"),
+            )
+            .unwrap();
+        };
+        let monitored =
+            MonitoredProcesses::new(vec![parse_selector("name:mysqld").unwrap()], dir.path());
+        let mut collector = RestartCollector::new(
+            monitored.clone(),
+            CrashLoopThresholds {
+                max_restarts: 2,
+                ..CrashLoopThresholds::default()
+            },
+        );
+
+        write_process(100, 1000);
+        monitored.refresh().await;
+        assert_eq!(
+            collector.collect().await.unwrap().status,
+            CheckStatus::Healthy
+        );
+        let mut statuses = Vec::new();
+        let mut result = None;
+        for pid in 101..=103u32 {
+            std::fs::remove_dir_all(dir.path().join((pid - 1).to_string())).unwrap();
+            write_process(pid, pid as u64 * 10);
+            monitored.refresh().await;
+            let r = collector.collect().await.unwrap();
+            statuses.push(r.status);
+            result = Some(r);
+        }
+        assert_eq!(
+            statuses,
+            [
+                CheckStatus::Degraded,
+                CheckStatus::Degraded,
+                CheckStatus::Unhealthy
+            ]
+        );
+        let result = result.unwrap();
+        assert_eq!(result.metadata["event"], "crash_loop");
+        match result.payload {
+            MetricPayload::Restarts(s) => {
+                assert_eq!(s.selectors[0].restarts_in_window, 3);
+                assert!(s.selectors[0].crash_loop);
+                assert_eq!(s.events[0].old_pid, Some(102));
+                assert_eq!(s.events[0].new_pid, 103);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use super::{
+    CheckStatus, CollectionResult, Collector, MetricPayload, RunQueueLatency, SchedstatSnapshot,
+};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::fs;
+
+/// Default run-queue wait thresholds, shared by
+/// [`SchedLatencyThresholds::default`] and the CLI.
+pub const DEFAULT_WAIT_DEGRADED_MS: f64 = 2.0;
+pub const DEFAULT_WAIT_UNHEALTHY_MS: f64 = 10.0;
+
+/// Average run-queue wait per timeslice, in milliseconds, at which the
+/// host is degraded or unhealthy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedLatencyThresholds {
+    pub wait_degraded_ms: f64,
+    pub wait_unhealthy_ms: f64,
+}
+
+impl Default for SchedLatencyThresholds {
+    fn default() -> Self {
+        Self {
+            wait_degraded_ms: DEFAULT_WAIT_DEGRADED_MS,
+            wait_unhealthy_ms: DEFAULT_WAIT_UNHEALTHY_MS,
+        }
+    }
+}
+
+/// Scheduler run-queue latency collector reading /proc/schedstat.
+pub struct SchedstatCollector {
+    proc_root: PathBuf,
+    thresholds: SchedLatencyThresholds,
+    prev: Option<(Instant, Vec<CpuSchedstat>)>,
+}
+
+/// Cumulative counters from one `cpuN` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CpuSchedstat {
+    cpu: u32,
+    running_ns: u64,
+    waiting_ns: u64,
+    timeslices: u64,
+}
+
+impl SchedstatCollector {
+    pub fn new(proc_root: impl Into<PathBuf>, thresholds: SchedLatencyThresholds) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            thresholds,
+            prev: None,
+        }
+    }
+
+    /// Parse the `cpuN` lines. Fields 7-9 after the name are time spent
+    /// running, time spent waiting on the run queue (both ns) and the
+    /// number of timeslices run; the layout is stable since version 15.
+    fn parse_schedstat(path: &str, content: &str) -> Result<Vec<CpuSchedstat>, CollectorError> {
+        let mut cpus = Vec::new();
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let Some(cpu) = parts
+                .next()
+                .and_then(|name| name.strip_prefix("cpu"))
+                .and_then(|n| n.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let fields: Vec<u64> = parts
+                .map(|f| f.parse::<u64>())
+                .collect::<Result<_, _>>()
+                .ok()
+                .filter(|f: &Vec<u64>| f.len() >= 9)
+                .ok_or_else(|| CollectorError::ParseError {
+                    path: path.into(),
+                    field: format!("cpu{cpu}"),
+                    raw: line.to_string(),
+                })?;
+            cpus.push(CpuSchedstat {
+                cpu,
+                running_ns: fields[6],
+                waiting_ns: fields[7],
+                timeslices: fields[8],
+            });
+        }
+        if cpus.is_empty() {
+            return Err(CollectorError::ParseError {
+                path: path.into(),
+                field: "cpu".into(),
+                raw: content.lines().next().unwrap_or_default().to_string(),
+            });
+        }
+        Ok(cpus)
+    }
+
+    /// Per-CPU latency over the interval. CPUs that went offline, or whose
+    /// counters went backwards after a hotplug, are left out.
+    fn latency(
+        prev: &[CpuSchedstat],
+        current: &[CpuSchedstat],
+        elapsed_secs: f64,
+    ) -> Vec<RunQueueLatency> {
+        current
+            .iter()
+            .filter_map(|c| {
+                let p = prev.iter().find(|p| p.cpu == c.cpu)?;
+                let waiting_ns = c.waiting_ns.checked_sub(p.waiting_ns)?;
+                let timeslices = c.timeslices.checked_sub(p.timeslices)?;
+                c.running_ns.checked_sub(p.running_ns)?;
+                Some(RunQueueLatency {
+                    cpu: c.cpu,
+                    avg_wait_per_timeslice_ms: (timeslices > 0)
+                        .then(|| waiting_ns as f64 / timeslices as f64 / 1e6),
+                    wait_ms_per_sec: waiting_ns as f64 / 1e6 / elapsed_secs,
+                    timeslices_per_sec: timeslices as f64 / elapsed_secs,
+                })
+            })
+            .collect()
+    }
+
+    fn evaluate(&self, snapshot: &SchedstatSnapshot) -> CheckStatus {
+        match snapshot.avg_wait_per_timeslice_ms {
+            Some(ms) if ms >= self.thresholds.wait_unhealthy_ms => CheckStatus::Unhealthy,
+            Some(ms) if ms >= self.thresholds.wait_degraded_ms => CheckStatus::Degraded,
+            _ => CheckStatus::Healthy,
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for SchedstatCollector {
+    fn name(&self) -> &'static str {
+        "schedstat"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let path = self.proc_root.join("schedstat");
+        let path_str = path.display().to_string();
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => Some(content),
+            // kernels built without CONFIG_SCHEDSTATS
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(CollectorError::ProcReadError {
+                    path: path_str,
+                    source: e,
+                })
+            }
+        };
+        let now = Instant::now();
+
+        let mut snapshot = SchedstatSnapshot::default();
+        if let Some(content) = content {
+            let sample = Self::parse_schedstat(&path_str, &content)?;
+            snapshot.supported = true;
+            match &self.prev {
+                None => snapshot.discarded_reason = Some("baseline sample".into()),
+                Some((prev_at, prev)) => {
+                    let elapsed = now.duration_since(*prev_at).as_secs_f64();
+                    if elapsed > 0.0 {
+                        snapshot.per_cpu = Self::latency(prev, &sample, elapsed);
+                    }
+                    let (wait_ms, slices) =
+                        snapshot.per_cpu.iter().fold((0.0, 0.0), |(w, s), c| {
+                            (w + c.wait_ms_per_sec, s + c.timeslices_per_sec)
+                        });
+                    snapshot.avg_wait_per_timeslice_ms = (slices > 0.0).then(|| wait_ms / slices);
+                    snapshot.worst_cpu = snapshot
+                        .per_cpu
+                        .iter()
+                        .filter_map(|c| Some((c.cpu, c.avg_wait_per_timeslice_ms?)))
+                        .max_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(cpu, _)| cpu);
+                }
+            }
+            self.prev = Some((now, sample));
+        }
+
+        let status = self.evaluate(&snapshot);
+        let message = if !snapshot.supported {
+            "schedstat not available (CONFIG_SCHEDSTATS disabled)".to_string()
+        } else if let Some(reason) = &snapshot.discarded_reason {
+            format!("interval discarded: {reason}")
+        } else {
+            let worst = snapshot.worst_cpu.and_then(|cpu| {
+                let c = snapshot.per_cpu.iter().find(|c| c.cpu == cpu)?;
+                Some(format!(
+                    " worst=cpu{} ({:.3} ms)",
+                    cpu, c.avg_wait_per_timeslice_ms?
+                ))
+            });
+            format!(
+                "avg run-queue wait per timeslice={}{}",
+                snapshot
+                    .avg_wait_per_timeslice_ms
+                    .map_or("n/a".to_string(), |ms| format!("{ms:.3} ms")),
+                worst.unwrap_or_default()
+            )
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Schedstat(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+version 15
+timestamp 4294877693
+cpu0 0 0 0 0 0 0 1000000000 20000000 1000
+domain0 00000003 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+cpu1 0 0 0 0 0 0 2000000000 10000000 4000
+domain0 00000003 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0";
+
+    #[test]
+    fn test_parse_schedstat() {
+        let cpus = SchedstatCollector::parse_schedstat("/proc/schedstat", SAMPLE).unwrap();
+        assert_eq!(cpus.len(), 2);
+        assert_eq!(cpus[0].running_ns, 1_000_000_000);
+        assert_eq!(cpus[1].waiting_ns, 10_000_000);
+        assert_eq!(cpus[1].timeslices, 4000);
+        assert!(SchedstatCollector::parse_schedstat("/proc/schedstat", "version 15\n").is_err());
+        assert!(SchedstatCollector::parse_schedstat("/proc/schedstat", "cpu0 1 2 3").is_err());
+    }
+
+    #[test]
+    fn test_latency_skips_regressed_cpus() {
+        let prev = SchedstatCollector::parse_schedstat("/proc/schedstat", SAMPLE).unwrap();
+        let mut curr = prev.clone();
+        // cpu0: 100 timeslices waited 500ms in total -> 5ms each
+        curr[0].waiting_ns += 500_000_000;
+        curr[0].timeslices += 100;
+        // cpu1 went offline and came back with fresh counters
+        curr[1].waiting_ns = 0;
+
+        let latency = SchedstatCollector::latency(&prev, &curr, 1.0);
+        assert_eq!(latency.len(), 1);
+        assert_eq!(latency[0].cpu, 0);
+        assert!((latency[0].avg_wait_per_timeslice_ms.unwrap() - 5.0).abs() < 1e-9);
+        assert!((latency[0].wait_ms_per_sec - 500.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("schedstat"), SAMPLE).unwrap();
+        let mut collector = SchedstatCollector::new(dir.path(), SchedLatencyThresholds::default());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("baseline sample"));
+
+        // 3ms wait per timeslice on cpu1, nothing scheduled on cpu0
+        std::fs::write(
+            dir.path().join("schedstat"),
+            SAMPLE.replace("10000000 4000", "310000000 4100"),
+        )
+        .unwrap();
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Degraded);
+        match result.payload {
+            MetricPayload::Schedstat(s) => {
+                assert!((s.avg_wait_per_timeslice_ms.unwrap() - 3.0).abs() < 1e-9);
+                assert_eq!(s.worst_cpu, Some(1));
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kernel_without_schedstats() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut collector = SchedstatCollector::new(dir.path(), SchedLatencyThresholds::default());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("not available"));
+    }
+}
@@ -0,0 +1,111 @@
+use super::{CheckStatus, CollectionResult, Collector, MetricPayload, ProcessSelectionSnapshot};
+use crate::errors::CollectorError;
+use crate::selectors::MonitoredProcesses;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// Reports which PIDs each configured process selector matched this cycle.
+/// A selector matching nothing is unhealthy: the process it describes is
+/// not running.
+pub struct ProcessSelectionCollector {
+    monitored: MonitoredProcesses,
+}
+
+impl ProcessSelectionCollector {
+    pub fn new(monitored: MonitoredProcesses) -> Self {
+        Self { monitored }
+    }
+
+    fn evaluate(snapshot: &ProcessSelectionSnapshot) -> (CheckStatus, String) {
+        let unmatched: Vec<&str> = snapshot
+            .selections
+            .iter()
+            .filter(|m| m.pids.is_empty())
+            .map(|m| m.selector.as_str())
+            .collect();
+
+        let status = if unmatched.is_empty() {
+            CheckStatus::Healthy
+        } else {
+            CheckStatus::Unhealthy
+        };
+
+        let summary: Vec<String> = snapshot
+            .selections
+            .iter()
+            .map(|m| format!("{}={:?}", m.selector, m.pids))
+            .collect();
+        let mut message = summary.join(" ");
+        if !unmatched.is_empty() {
+            message = format!("no process matched {} | {}", unmatched.join(", "), message);
+        }
+        (status, message)
+    }
+}
+
+#[async_trait]
+impl Collector for ProcessSelectionCollector {
+    fn name(&self) -> &'static str {
+        "process_selection"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let selections = self.monitored.matches();
+        let snapshot = ProcessSelectionSnapshot { selections };
+        let (status, message) = Self::evaluate(&snapshot);
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::ProcessSelection(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selectors::{parse_selector, MonitoredProcesses, SelectorMatch};
+
+    #[test]
+    fn test_unmatched_selector_is_unhealthy() {
+        let snapshot = ProcessSelectionSnapshot {
+            selections: vec![
+                SelectorMatch {
+                    selector: "name:mysqld".into(),
+                    pids: vec![100],
+                },
+                SelectorMatch {
+                    selector: "pidfile:/run/mysqld/mysqld.pid".into(),
+                    pids: vec![],
+                },
+            ],
+        };
+        let (status, message) = ProcessSelectionCollector::evaluate(&snapshot);
+        assert_eq!(status, CheckStatus::Unhealthy);
+        assert!(message.contains("no process matched pidfile:/run/mysqld/mysqld.pid"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_against_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let p = dir.path().join("42");
+        std::fs::create_dir_all(&p).unwrap();
+        std::fs::write(p.join("comm"), "mysqld\n").unwrap();
+
+        let monitored =
+            MonitoredProcesses::new(vec![parse_selector("name:mysqld").unwrap()], dir.path());
+        monitored.refresh().await;
+        let mut collector = ProcessSelectionCollector::new(monitored);
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::ProcessSelection(s) => assert_eq!(s.selections[0].pids, vec![42]),
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use super::leak::LeakCollector;
+use super::memory::MemoryCollector;
+use super::{CheckStatus, CollectionResult, Collector, MetricPayload, SlabCache, SlabSnapshot};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::fs;
+
+/// Caches reported in the snapshot, largest first.
+const TOP_CACHES: usize = 10;
+/// Rolling window the growth trend is fitted over.
+const GROWTH_WINDOW: Duration = Duration::from_secs(30 * 60);
+/// Growth must be visible for this long before a cache is flagged.
+const GROWTH_SUSTAINED: Duration = Duration::from_secs(15 * 60);
+const GROWTH_MIN_BYTES_PER_HOUR: f64 = 16.0 * 1024.0 * 1024.0;
+const GROWTH_MIN_R_SQUARED: f64 = 0.8;
+/// Reading /proc/slabinfo walks every cache under the kernel's slab mutex,
+/// so it is read at this rate and the last entries reused in between.
+const SLABINFO_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Kernel slab collector. Parses /proc/slabinfo for per-cache sizes and
+/// tracks their growth; slabinfo is root-only, so without it falls back to
+/// the SReclaimable/SUnreclaim totals in /proc/meminfo.
+pub struct SlabCollector {
+    proc_root: PathBuf,
+    /// Size samples per cache (or per meminfo total in fallback mode).
+    history: HashMap<String, VecDeque<(Instant, u64)>>,
+    last_slabinfo_at: Option<Instant>,
+    /// Entries from the last slabinfo read, None when it was unreadable or
+    /// could not be parsed.
+    slabinfo: Option<Vec<SlabEntry>>,
+}
+
+/// One line of /proc/slabinfo.
+#[derive(Debug, Clone, PartialEq)]
+struct SlabEntry {
+    name: String,
+    active_objs: u64,
+    num_objs: u64,
+    obj_size: u64,
+}
+
+impl SlabCollector {
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            history: HashMap::new(),
+            last_slabinfo_at: None,
+            slabinfo: None,
+        }
+    }
+
+    fn slabinfo_due(&self, now: Instant) -> bool {
+        self.last_slabinfo_at
+            .is_none_or(|at| now.duration_since(at) >= SLABINFO_INTERVAL)
+    }
+
+    /// Read and parse /proc/slabinfo. Unreadable (not root) and unparsable
+    /// content both yield None so the caller falls back to meminfo.
+    async fn read_slabinfo(&self) -> Option<Vec<SlabEntry>> {
+        let path = self.proc_root.join("slabinfo");
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::debug!(path = %path.display(), error = %e, "slabinfo not readable, using meminfo totals");
+                return None;
+            }
+        };
+        match Self::parse_slabinfo(&path.display().to_string(), &content) {
+            Ok(entries) => Some(entries),
+            Err(e) => {
+                tracing::warn!(error = %e, "slabinfo not parsable, using meminfo totals");
+                None
+            }
+        }
+    }
+
+    /// Parse slabinfo version 2.x:
+    /// `<name> <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables ...`
+    fn parse_slabinfo(path: &str, content: &str) -> Result<Vec<SlabEntry>, CollectorError> {
+        let mut lines = content.lines();
+        match lines.next() {
+            Some(header) if header.starts_with("slabinfo - version: 2.") => {}
+            other => {
+                return Err(CollectorError::ParseError {
+                    path: path.into(),
+                    field: "version".into(),
+                    raw: other.unwrap_or_default().into(),
+                })
+            }
+        }
+        let mut entries = Vec::new();
+        for line in lines.filter(|l| !l.starts_with('#') && !l.trim().is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| fields.get(i).and_then(|f| f.parse::<u64>().ok());
+            let (Some(active_objs), Some(num_objs), Some(obj_size)) =
+                (number(1), number(2), number(3))
+            else {
+                return Err(CollectorError::ParseError {
+                    path: path.into(),
+                    field: fields.first().copied().unwrap_or(line).into(),
+                    raw: line.into(),
+                });
+            };
+            entries.push(SlabEntry {
+                name: fields[0].to_string(),
+                active_objs,
+                num_objs,
+                obj_size,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Record the sizes taken at `now` and forget caches that are gone
+    /// (a module was unloaded, or the source switched).
+    fn record(&mut self, now: Instant, sizes: &[(String, u64)]) {
+        self.history
+            .retain(|name, _| sizes.iter().any(|(n, _)| n == name));
+        for (name, bytes) in sizes {
+            let samples = self.history.entry(name.clone()).or_default();
+            samples.push_back((now, *bytes));
+            while samples
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) > GROWTH_WINDOW)
+            {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Fitted growth in bytes per hour, and whether it is steady and
+    /// sustained enough to flag.
+    fn growth(&self, name: &str) -> (Option<f64>, bool) {
+        let Some(samples) = self.history.get(name) else {
+            return (None, false);
+        };
+        let Some(&(first, _)) = samples.front() else {
+            return (None, false);
+        };
+        let span = samples
+            .back()
+            .map_or(Duration::ZERO, |(at, _)| at.duration_since(first));
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|(at, bytes)| (at.duration_since(first).as_secs_f64(), *bytes as f64))
+            .collect();
+        match LeakCollector::linear_fit(&points) {
+            Some((slope, r_squared)) => {
+                let per_hour = slope * 3600.0;
+                let growing = span >= GROWTH_SUSTAINED
+                    && r_squared >= GROWTH_MIN_R_SQUARED
+                    && per_hour >= GROWTH_MIN_BYTES_PER_HOUR;
+                (Some(per_hour), growing)
+            }
+            None => (None, false),
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for SlabCollector {
+    fn name(&self) -> &'static str {
+        "slab"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let meminfo_path = self.proc_root.join("meminfo");
+        let meminfo =
+            fs::read_to_string(&meminfo_path)
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: meminfo_path.display().to_string(),
+                    source: e,
+                })?;
+        let meminfo = MemoryCollector::parse_meminfo(&meminfo)?;
+        let meminfo_bytes = |key: &str| meminfo.get(key).map(|kb| kb * 1024);
+
+        let now = Instant::now();
+        // meminfo totals are cheap and sampled every cycle; per-cache sizes
+        // only get a new sample when slabinfo is actually re-read
+        let fresh = self.slabinfo_due(now);
+        if fresh {
+            self.slabinfo = self.read_slabinfo().await;
+            self.last_slabinfo_at = Some(now);
+        }
+        let entries = self.slabinfo.clone();
+
+        let (source, caches) = match entries {
+            Some(entries) => (
+                "slabinfo",
+                entries
+                    .into_iter()
+                    .map(|e| SlabCache {
+                        bytes: e.num_objs * e.obj_size,
+                        name: e.name,
+                        active_objs: Some(e.active_objs),
+                        num_objs: Some(e.num_objs),
+                        obj_size: Some(e.obj_size),
+                        growth_bytes_per_hour: None,
+                        growing: false,
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            None => (
+                "meminfo",
+                ["SReclaimable", "SUnreclaim"]
+                    .into_iter()
+                    .filter_map(|key| {
+                        Some(SlabCache {
+                            name: key.to_string(),
+                            bytes: meminfo_bytes(key)?,
+                            active_objs: None,
+                            num_objs: None,
+                            obj_size: None,
+                            growth_bytes_per_hour: None,
+                            growing: false,
+                        })
+                    })
+                    .collect(),
+            ),
+        };
+
+        if fresh || source == "meminfo" {
+            let sizes: Vec<(String, u64)> =
+                caches.iter().map(|c| (c.name.clone(), c.bytes)).collect();
+            self.record(now, &sizes);
+        }
+        let mut caches: Vec<SlabCache> = caches
+            .into_iter()
+            .map(|mut c| {
+                (c.growth_bytes_per_hour, c.growing) = self.growth(&c.name);
+                c
+            })
+            .collect();
+        caches.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+        let growing: Vec<String> = caches
+            .iter()
+            .filter(|c| c.growing)
+            .map(|c| c.name.clone())
+            .collect();
+        // growing caches are always reported, even when not among the largest
+        let top_caches: Vec<SlabCache> = caches
+            .iter()
+            .enumerate()
+            .filter(|(i, c)| *i < TOP_CACHES || c.growing)
+            .map(|(_, c)| c.clone())
+            .collect();
+
+        let snapshot = SlabSnapshot {
+            source: source.to_string(),
+            total_bytes: meminfo_bytes("Slab"),
+            reclaimable_bytes: meminfo_bytes("SReclaimable"),
+            unreclaimable_bytes: meminfo_bytes("SUnreclaim"),
+            top_caches,
+            growing,
+        };
+
+        let mb = |b: u64| b / (1024 * 1024);
+        let (status, message) = if snapshot.growing.is_empty() {
+            (
+                CheckStatus::Healthy,
+                format!(
+                    "slab={} MB (unreclaimable {} MB) from {}",
+                    mb(snapshot.total_bytes.unwrap_or(0)),
+                    mb(snapshot.unreclaimable_bytes.unwrap_or(0)),
+                    snapshot.source
+                ),
+            )
+        } else {
+            let growing: Vec<String> = snapshot
+                .top_caches
+                .iter()
+                .filter(|c| c.growing)
+                .map(|c| {
+                    format!(
+                        "{} {} MB +{:.0} MB/h",
+                        c.name,
+                        mb(c.bytes),
+                        c.growth_bytes_per_hour.unwrap_or(0.0) / (1024.0 * 1024.0)
+                    )
+                })
+                .collect();
+            (
+                CheckStatus::Degraded,
+                format!("steady slab growth: {}", growing.join("; ")),
+            )
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Slab(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    const SLABINFO: &str = "\
+slabinfo - version: 2.1
+# name            <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>
+dentry            1500000 1600000    192   21    1 : tunables    0    0    0 : slabdata  76190  76190      0
+inode_cache        40000  41000    600   27    4 : tunables    0    0    0 : slabdata   1518   1518      0
+kmalloc-64         90000 100000     64   64    1 : tunables    0    0    0 : slabdata   1562   1562      0
+";
+
+    const MEMINFO: &str = "Slab:  600000 kB\nSReclaimable:  450000 kB\nSUnreclaim:  150000 kB\n";
+
+    #[test]
+    fn test_parse_slabinfo() {
+        let entries = SlabCollector::parse_slabinfo("/proc/slabinfo", SLABINFO).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            SlabEntry {
+                name: "dentry".into(),
+                active_objs: 1500000,
+                num_objs: 1600000,
+                obj_size: 192,
+            }
+        );
+        assert!(
+            SlabCollector::parse_slabinfo("/proc/slabinfo", "slabinfo - version: 1.1\n").is_err()
+        );
+        assert!(SlabCollector::parse_slabinfo(
+            "/proc/slabinfo",
+            "slabinfo - version: 2.1\ndentry lots\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_steady_growth_is_flagged() {
+        let mut c = SlabCollector::new("/proc");
+        let start = Instant::now();
+        // dentry +60 MiB per hour, kmalloc-64 flat, over 20 minutes
+        for i in 0..=4u64 {
+            c.record(
+                start + Duration::from_secs(i * 300),
+                &[
+                    ("dentry".into(), 300 * MIB + i * 5 * MIB),
+                    ("kmalloc-64".into(), 6 * MIB),
+                ],
+            );
+        }
+        let (rate, growing) = c.growth("dentry");
+        assert!(growing);
+        assert!((rate.unwrap() / MIB as f64 - 60.0).abs() < 0.1);
+        assert!(!c.growth("kmalloc-64").1);
+    }
+
+    #[test]
+    fn test_short_growth_is_not_flagged() {
+        let mut c = SlabCollector::new("/proc");
+        let start = Instant::now();
+        for i in 0..=2u64 {
+            c.record(
+                start + Duration::from_secs(i * 300),
+                &[("dentry".into(), 300 * MIB + i * 5 * MIB)],
+            );
+        }
+        assert!(!c.growth("dentry").1);
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_slabinfo() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("slabinfo"), SLABINFO).unwrap();
+        std::fs::write(dir.path().join("meminfo"), MEMINFO).unwrap();
+
+        let result = SlabCollector::new(dir.path()).collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Slab(s) => {
+                assert_eq!(s.source, "slabinfo");
+                assert_eq!(s.unreclaimable_bytes, Some(150000 * 1024));
+                let names: Vec<&str> = s.top_caches.iter().map(|c| c.name.as_str()).collect();
+                assert_eq!(names, ["dentry", "inode_cache", "kmalloc-64"]);
+                assert_eq!(s.top_caches[0].bytes, 1600000 * 192);
+                assert_eq!(s.top_caches[0].growth_bytes_per_hour, None);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_meminfo() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("meminfo"), MEMINFO).unwrap();
+
+        let result = SlabCollector::new(dir.path()).collect().await.unwrap();
+        match result.payload {
+            MetricPayload::Slab(s) => {
+                assert_eq!(s.source, "meminfo");
+                assert_eq!(s.top_caches.len(), 2);
+                assert_eq!(s.top_caches[0].name, "SReclaimable");
+                assert_eq!(s.top_caches[0].num_objs, None);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unparsable_slabinfo_falls_back_to_meminfo() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("slabinfo"), "slabinfo - version: 3.0\n").unwrap();
+        std::fs::write(dir.path().join("meminfo"), MEMINFO).unwrap();
+
+        let result = SlabCollector::new(dir.path()).collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        match result.payload {
+            MetricPayload::Slab(s) => {
+                assert_eq!(s.source, "meminfo");
+                assert_eq!(s.top_caches.len(), 2);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slabinfo_reused_between_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("slabinfo"), SLABINFO).unwrap();
+        std::fs::write(dir.path().join("meminfo"), MEMINFO).unwrap();
+
+        let mut c = SlabCollector::new(dir.path());
+        c.collect().await.unwrap();
+        std::fs::remove_file(dir.path().join("slabinfo")).unwrap();
+
+        let result = c.collect().await.unwrap();
+        match result.payload {
+            MetricPayload::Slab(s) => {
+                assert_eq!(s.source, "slabinfo");
+                assert_eq!(s.top_caches[0].name, "dentry");
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+        // the reused entries are not recorded as a new sample
+        assert_eq!(c.history["dentry"].len(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+use super::{CheckStatus, CollectionResult, Collector, MetricPayload, VmstatSnapshot};
+use crate::errors::CollectorError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::fs;
+
+/// Pages swapped in per second. Swap-in means something needed memory that
+/// was pushed out, which is what thrashing looks like from outside.
+const SWAPIN_DEGRADED_PER_SEC: f64 = 100.0;
+const SWAPIN_UNHEALTHY_PER_SEC: f64 = 1000.0;
+/// Allocations that stalled in direct reclaim per second.
+const ALLOCSTALL_DEGRADED_PER_SEC: f64 = 1.0;
+const ALLOCSTALL_UNHEALTHY_PER_SEC: f64 = 50.0;
+/// Major faults per second. Also caused by cold file reads, so only ever
+/// degrades.
+const MAJFAULT_DEGRADED_PER_SEC: f64 = 1000.0;
+
+/// Swap and paging activity collector reading /proc/vmstat. Status follows
+/// swap and reclaim traffic, not swap occupancy.
+pub struct VmstatCollector {
+    proc_root: PathBuf,
+    prev: Option<(Instant, PagingCounters)>,
+}
+
+/// Cumulative counters, with per-zone and per-type variants summed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PagingCounters {
+    pswpin: u64,
+    pswpout: u64,
+    pgmajfault: u64,
+    pgscan_kswapd: u64,
+    pgscan_direct: u64,
+    pgsteal_kswapd: u64,
+    pgsteal_direct: u64,
+    allocstall: u64,
+}
+
+impl PagingCounters {
+    fn fields(&self) -> [u64; 8] {
+        [
+            self.pswpin,
+            self.pswpout,
+            self.pgmajfault,
+            self.pgscan_kswapd,
+            self.pgscan_direct,
+            self.pgsteal_kswapd,
+            self.pgsteal_direct,
+            self.allocstall,
+        ]
+    }
+}
+
+impl VmstatCollector {
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            prev: None,
+        }
+    }
+
+    /// Parse /proc/vmstat, or any flat-keyed `name value` file such as a
+    /// cgroup's memory.stat or memory.events, into a map.
+    pub(crate) fn parse_vmstat(
+        path: &str,
+        content: &str,
+    ) -> Result<HashMap<String, u64>, CollectorError> {
+        let mut map = HashMap::new();
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let parsed = line
+                .split_once(' ')
+                .and_then(|(key, value)| Some((key, value.trim().parse::<u64>().ok()?)));
+            let Some((key, value)) = parsed else {
+                return Err(CollectorError::ParseError {
+                    path: path.into(),
+                    field: line.split(' ').next().unwrap_or(line).into(),
+                    raw: line.into(),
+                });
+            };
+            map.insert(key.to_string(), value);
+        }
+        Ok(map)
+    }
+
+    /// Sum every counter named `prefix` or `prefix_<suffix>`. Older kernels
+    /// split pgscan/pgsteal/allocstall per zone (pgscan_direct_normal,
+    /// allocstall_dma32, ...), newer ones per node or not at all.
+    fn sum_prefixed(map: &HashMap<String, u64>, prefix: &str, exclude: &[&str]) -> u64 {
+        map.iter()
+            .filter(|(key, _)| {
+                (key.as_str() == prefix || key.starts_with(&format!("{prefix}_")))
+                    && !exclude.contains(&key.as_str())
+            })
+            .map(|(_, v)| *v)
+            .sum()
+    }
+
+    fn counters(map: &HashMap<String, u64>) -> PagingCounters {
+        let get = |key: &str| map.get(key).copied().unwrap_or(0);
+        PagingCounters {
+            pswpin: get("pswpin"),
+            pswpout: get("pswpout"),
+            pgmajfault: get("pgmajfault"),
+            pgscan_kswapd: Self::sum_prefixed(map, "pgscan_kswapd", &[]),
+            // pgscan_direct_throttle counts throttling events, not pages
+            pgscan_direct: Self::sum_prefixed(map, "pgscan_direct", &["pgscan_direct_throttle"]),
+            pgsteal_kswapd: Self::sum_prefixed(map, "pgsteal_kswapd", &[]),
+            pgsteal_direct: Self::sum_prefixed(map, "pgsteal_direct", &[]),
+            allocstall: Self::sum_prefixed(map, "allocstall", &[]),
+        }
+    }
+
+    fn rates(prev: &PagingCounters, current: &PagingCounters, elapsed_secs: f64) -> VmstatSnapshot {
+        let rate = |c: u64, p: u64| c.saturating_sub(p) as f64 / elapsed_secs;
+        let scanned = rate(current.pgscan_kswapd, prev.pgscan_kswapd)
+            + rate(current.pgscan_direct, prev.pgscan_direct);
+        let stolen = rate(current.pgsteal_kswapd, prev.pgsteal_kswapd)
+            + rate(current.pgsteal_direct, prev.pgsteal_direct);
+        VmstatSnapshot {
+            pswpin_per_sec: rate(current.pswpin, prev.pswpin),
+            pswpout_per_sec: rate(current.pswpout, prev.pswpout),
+            pgmajfault_per_sec: rate(current.pgmajfault, prev.pgmajfault),
+            pgscan_kswapd_per_sec: rate(current.pgscan_kswapd, prev.pgscan_kswapd),
+            pgscan_direct_per_sec: rate(current.pgscan_direct, prev.pgscan_direct),
+            pgsteal_kswapd_per_sec: rate(current.pgsteal_kswapd, prev.pgsteal_kswapd),
+            pgsteal_direct_per_sec: rate(current.pgsteal_direct, prev.pgsteal_direct),
+            allocstall_per_sec: rate(current.allocstall, prev.allocstall),
+            reclaim_efficiency_pct: (scanned > 0.0).then(|| stolen / scanned * 100.0),
+            discarded_reason: None,
+        }
+    }
+
+    fn evaluate(snapshot: &VmstatSnapshot) -> CheckStatus {
+        if snapshot.pswpin_per_sec >= SWAPIN_UNHEALTHY_PER_SEC
+            || snapshot.allocstall_per_sec >= ALLOCSTALL_UNHEALTHY_PER_SEC
+        {
+            CheckStatus::Unhealthy
+        } else if snapshot.pswpin_per_sec >= SWAPIN_DEGRADED_PER_SEC
+            || snapshot.allocstall_per_sec >= ALLOCSTALL_DEGRADED_PER_SEC
+            || snapshot.pgmajfault_per_sec >= MAJFAULT_DEGRADED_PER_SEC
+        {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Healthy
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for VmstatCollector {
+    fn name(&self) -> &'static str {
+        "vmstat"
+    }
+
+    async fn collect(&mut self) -> Result<CollectionResult, CollectorError> {
+        let path = self.proc_root.join("vmstat");
+        let path_str = path.display().to_string();
+        let content =
+            fs::read_to_string(&path)
+                .await
+                .map_err(|e| CollectorError::ProcReadError {
+                    path: path_str.clone(),
+                    source: e,
+                })?;
+        let now = Instant::now();
+        let counters = Self::counters(&Self::parse_vmstat(&path_str, &content)?);
+
+        let discarded = |reason: &str| VmstatSnapshot {
+            discarded_reason: Some(reason.to_string()),
+            ..VmstatSnapshot::default()
+        };
+        let snapshot = match self.prev {
+            None => discarded("baseline sample"),
+            Some((_, prev))
+                if counters
+                    .fields()
+                    .iter()
+                    .zip(prev.fields())
+                    .any(|(c, p)| *c < p) =>
+            {
+                discarded("counter reset")
+            }
+            Some((prev_at, prev)) => {
+                let elapsed = now.duration_since(prev_at).as_secs_f64();
+                if elapsed > 0.0 {
+                    Self::rates(&prev, &counters, elapsed)
+                } else {
+                    discarded("zero-length interval")
+                }
+            }
+        };
+        self.prev = Some((now, counters));
+
+        let status = Self::evaluate(&snapshot);
+        let message = match &snapshot.discarded_reason {
+            Some(reason) => format!("interval discarded: {reason}"),
+            None => format!(
+                "swap in/out={:.0}/{:.0} pages/s majfault={:.0}/s \
+                 scan kswapd/direct={:.0}/{:.0} pages/s allocstall={:.1}/s",
+                snapshot.pswpin_per_sec,
+                snapshot.pswpout_per_sec,
+                snapshot.pgmajfault_per_sec,
+                snapshot.pgscan_kswapd_per_sec,
+                snapshot.pgscan_direct_per_sec,
+                snapshot.allocstall_per_sec
+            ),
+        };
+
+        Ok(CollectionResult {
+            check_name: self.name().to_string(),
+            status,
+            message,
+            metadata: HashMap::new(),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Vmstat(snapshot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_VMSTAT: &str = "\
+nr_free_pages 123456
+pswpin 100
+pswpout 200
+pgmajfault 300
+allocstall_dma32 1
+allocstall_normal 2
+pgsteal_kswapd 400
+pgsteal_direct 50
+pgscan_kswapd 800
+pgscan_direct 100
+pgscan_direct_throttle 7
+pgscan_anon 600
+pgscan_file 300
+oom_kill 0";
+
+    fn counters(content: &str) -> PagingCounters {
+        VmstatCollector::counters(&VmstatCollector::parse_vmstat("/proc/vmstat", content).unwrap())
+    }
+
+    #[test]
+    fn test_counters_sum_variants() {
+        let c = counters(SAMPLE_VMSTAT);
+        assert_eq!(c.pswpin, 100);
+        assert_eq!(c.allocstall, 3);
+        // throttle events and the anon/file split are not double counted
+        assert_eq!(c.pgscan_direct, 100);
+        assert_eq!(c.pgscan_kswapd, 800);
+
+        // pre-4.8 per-zone layout
+        let old = counters("pgscan_kswapd_normal 10\npgscan_kswapd_dma32 5\nallocstall 4\n");
+        assert_eq!(old.pgscan_kswapd, 15);
+        assert_eq!(old.allocstall, 4);
+    }
+
+    #[test]
+    fn test_parse_vmstat_rejects_garbage() {
+        assert!(VmstatCollector::parse_vmstat("/proc/vmstat", "pswpin lots").is_err());
+    }
+
+    #[test]
+    fn test_rates_and_thrashing() {
+        let prev = counters(SAMPLE_VMSTAT);
+        let mut curr = prev;
+        curr.pswpin += 2000;
+        curr.pgscan_kswapd += 1000;
+        curr.pgsteal_kswapd += 250;
+        let snapshot = VmstatCollector::rates(&prev, &curr, 1.0);
+        assert!((snapshot.pswpin_per_sec - 2000.0).abs() < 1e-9);
+        assert_eq!(snapshot.reclaim_efficiency_pct, Some(25.0));
+        assert_eq!(VmstatCollector::evaluate(&snapshot), CheckStatus::Unhealthy);
+
+        // heavy swap-out alone is not thrashing
+        let mut curr = prev;
+        curr.pswpout += 5000;
+        let snapshot = VmstatCollector::rates(&prev, &curr, 1.0);
+        assert_eq!(VmstatCollector::evaluate(&snapshot), CheckStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_collect_from_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("vmstat"), SAMPLE_VMSTAT).unwrap();
+        let mut collector = VmstatCollector::new(dir.path());
+        let result = collector.collect().await.unwrap();
+        assert_eq!(result.status, CheckStatus::Healthy);
+        assert!(result.message.contains("baseline sample"));
+
+        std::fs::write(
+            dir.path().join("vmstat"),
+            SAMPLE_VMSTAT.replace("pswpin 100", "pswpin 0"),
+        )
+        .unwrap();
+        let result = collector.collect().await.unwrap();
+        assert!(result.message.contains("counter reset"));
+    }
+}
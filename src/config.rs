@@ -1,14 +1,33 @@
+use crate::collectors::cpu::{self, StealThresholds};
+use crate::collectors::leak::{self, LeakThresholds};
+use crate::collectors::psi::{self, PsiThresholds};
+use crate::collectors::restart::{self, CrashLoopThresholds};
+use crate::collectors::schedstat::{self, SchedLatencyThresholds};
+use crate::identity::AgentIdentity;
+use crate::labels::parse_label;
+use crate::maintenance::{parse_schedule, MaintenanceSchedule, MAX_WINDOW_MINUTES};
+use crate::secrets::{Secret, SecretValueParser};
+use crate::selectors::{parse_selector, ProcessSelector};
 use clap::Parser;
+use std::path::PathBuf;
 use std::time::Duration;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "infra_health_agent", version, about)]
 pub struct Config {
     /// Unique identifier for this agent instance.
-    /// if none provided, default to hostname.
+    /// if none provided, default to the persisted agent UUID.
     #[arg(long, env = "INFRA_HEALTH_AGENT_ID")]
     pub agent_id: Option<String>,
 
+    /// Directory for agent state that must survive restarts (identity, ...).
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_STATE_DIR",
+        default_value = "/var/lib/infra-health-agent"
+    )]
+    pub state_dir: PathBuf,
+
     /// Telemetry collection interval in milliseconds.
     #[arg(long, env = "INFRA_HEALTH_COLLECT_INTERVAL_MS", default_value_t = 5000)]
     pub collect_interval_ms: u64,
@@ -17,9 +36,37 @@ pub struct Config {
     #[arg(long, env = "INFRA_HEALTH_CHANNEL_BUFFER", default_value_t = 256)]
     pub channel_buffer_size: usize,
 
-    /// Comma-separated list of PIDs to monitor.
-    #[arg(long, env = "INFRA_HEALTH_MONITORED_PIDS", value_delimiter = ',')]
-    pub monitored_pids: Vec<u32>,
+    /// Semicolon-separated process selectors to monitor, re-resolved every cycle:
+    /// name:<comm>, exe:<path>, cmdline:<regex>, pidfile:<path>, user:<name|uid>,
+    /// cgroup:<path> or pid:<pid>. Not comma-separated, as cmdline regexes may
+    /// contain commas (`{1,3}`); the flag can also be repeated. user: names are
+    /// looked up in the agent's own passwd, so prefer numeric uids in a container.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_MONITORED_PROCESSES",
+        value_delimiter = ';',
+        value_parser = parse_selector
+    )]
+    pub monitored_processes: Vec<ProcessSelector>,
+
+    /// Root of the proc filesystem (the host's /proc when running in a container).
+    #[arg(long, env = "HOST_PROC", default_value = "/proc")]
+    pub proc_root: PathBuf,
+
+    /// Root of the sysfs filesystem (the host's /sys when running in a container).
+    #[arg(long, env = "HOST_SYS", default_value = "/sys")]
+    pub sys_root: PathBuf,
+
+    /// cgroup v2 path (relative to the cgroup2 mount under HOST_SYS) whose CPU
+    /// quota, memory limit and throttling are reported, e.g. /system.slice/mysql.service.
+    /// if none provided, default to the agent's own cgroup, found through
+    /// /proc/self/cgroup under /sys/fs/cgroup.
+    #[arg(long, env = "INFRA_HEALTH_CGROUP")]
+    pub cgroup: Option<String>,
+
+    /// Kernel log device read for OOM-kill victims (needs CAP_SYSLOG).
+    #[arg(long, env = "INFRA_HEALTH_KMSG_PATH", default_value = "/dev/kmsg")]
+    pub kmsg_path: PathBuf,
 
     /// Enable JSON structured logging.
     #[arg(long, env = "INFRA_HEALTH_JSON_LOGS", default_value_t = false)]
@@ -32,23 +79,336 @@ pub struct Config {
     /// Retry backoff base in milliseconds.
     #[arg(long, env = "INFRA_HEALTH_RETRY_BACKOFF_MS", default_value_t = 500)]
     pub retry_backoff_ms: u64,
+
+    /// Static labels attached to every report, as comma-separated key=value pairs
+    /// (e.g. region=westeurope,cluster=mysql-01,role=primary,server_id=7).
+    #[arg(long, env = "INFRA_HEALTH_LABELS", value_delimiter = ',', value_parser = parse_label)]
+    pub labels: Vec<(String, String)>,
+
+    /// Maximum number of static + dynamic labels merged into a report.
+    #[arg(long, env = "INFRA_HEALTH_MAX_LABELS", default_value_t = 16)]
+    pub max_labels: usize,
+
+    /// mysqld binary queried for the `mysqld_version` label.
+    #[arg(long, env = "INFRA_HEALTH_MYSQLD_BIN", default_value = "mysqld")]
+    pub mysqld_bin: String,
+
+    /// Password for MySQL health checks, given as a source rather than a value:
+    /// file:<path>, env:<var> or cred:<name> (systemd LoadCredential).
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_MYSQL_PASSWORD",
+        value_parser = SecretValueParser,
+        hide_env_values = true
+    )]
+    pub mysql_password: Option<Secret>,
+
+    /// Auth token for report sinks, same source syntax as --mysql-password.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_SINK_TOKEN",
+        value_parser = SecretValueParser,
+        hide_env_values = true
+    )]
+    pub sink_token: Option<Secret>,
+
+    /// Recurring maintenance windows, ';'-separated. Each is a UTC cron start
+    /// (minute hour day-of-month month day-of-week) plus a duration, e.g. "0 2 * * 0 90m".
+    #[arg(
+        long = "maintenance-window",
+        env = "INFRA_HEALTH_MAINTENANCE_WINDOWS",
+        value_delimiter = ';',
+        value_parser = parse_schedule
+    )]
+    pub maintenance_windows: Vec<MaintenanceSchedule>,
+
+    /// Marker file that puts the agent in maintenance while present. May hold an
+    /// RFC 3339 end time; otherwise the window ends the default duration after its mtime.
+    #[arg(long, env = "INFRA_HEALTH_MAINTENANCE_FILE")]
+    pub maintenance_file: Option<PathBuf>,
+
+    /// Length in minutes of maintenance windows opened by SIGUSR1 or an undated marker file,
+    /// at most 24 hours.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_MAINTENANCE_DEFAULT_MINS",
+        default_value_t = 60,
+        value_parser = clap::value_parser!(u64).range(1..=MAX_WINDOW_MINUTES as u64)
+    )]
+    pub maintenance_default_mins: u64,
+
+    /// CPU steal percentage above which the host is degraded.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_STEAL_DEGRADED_PCT",
+        default_value_t = cpu::DEFAULT_STEAL_DEGRADED_PCT,
+        value_parser = parse_pct
+    )]
+    pub steal_degraded_pct: f64,
+
+    /// CPU steal percentage above which the host is unhealthy.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_STEAL_UNHEALTHY_PCT",
+        default_value_t = cpu::DEFAULT_STEAL_UNHEALTHY_PCT,
+        value_parser = parse_pct
+    )]
+    pub steal_unhealthy_pct: f64,
+
+    /// PSI `some` avg60 stall percentage above which a resource is degraded.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_PSI_SOME_DEGRADED_PCT",
+        default_value_t = psi::DEFAULT_SOME_DEGRADED_PCT
+    )]
+    pub psi_some_degraded_pct: f64,
+
+    /// PSI `some` avg60 stall percentage above which a resource is unhealthy.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_PSI_SOME_UNHEALTHY_PCT",
+        default_value_t = psi::DEFAULT_SOME_UNHEALTHY_PCT
+    )]
+    pub psi_some_unhealthy_pct: f64,
+
+    /// PSI `full` avg60 stall percentage above which a resource is degraded.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_PSI_FULL_DEGRADED_PCT",
+        default_value_t = psi::DEFAULT_FULL_DEGRADED_PCT
+    )]
+    pub psi_full_degraded_pct: f64,
+
+    /// PSI `full` avg60 stall percentage above which a resource is unhealthy.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_PSI_FULL_UNHEALTHY_PCT",
+        default_value_t = psi::DEFAULT_FULL_UNHEALTHY_PCT
+    )]
+    pub psi_full_unhealthy_pct: f64,
+
+    /// Average run-queue wait per timeslice (ms) above which the host is degraded.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_SCHED_WAIT_DEGRADED_MS",
+        default_value_t = schedstat::DEFAULT_WAIT_DEGRADED_MS
+    )]
+    pub sched_wait_degraded_ms: f64,
+
+    /// Average run-queue wait per timeslice (ms) above which the host is unhealthy.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_SCHED_WAIT_UNHEALTHY_MS",
+        default_value_t = schedstat::DEFAULT_WAIT_UNHEALTHY_MS
+    )]
+    pub sched_wait_unhealthy_ms: f64,
+
+    /// Rolling window, in minutes, over which monitored process memory is trended.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_LEAK_WINDOW_MINS",
+        default_value_t = leak::DEFAULT_WINDOW_MINS,
+        value_parser = clap::value_parser!(u64).range(1..=leak::MAX_WINDOW_MINS)
+    )]
+    pub leak_window_mins: u64,
+
+    /// Minutes of steady growth required before a process is reported as leaking.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_LEAK_SUSTAINED_MINS",
+        default_value_t = leak::DEFAULT_SUSTAINED_MINS,
+        value_parser = clap::value_parser!(u64).range(..=leak::MAX_WINDOW_MINS)
+    )]
+    pub leak_sustained_mins: u64,
+
+    /// Growth rate (MB per hour) above which steady growth counts as a leak.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_LEAK_MIN_GROWTH_MB_PER_HOUR",
+        default_value_t = leak::DEFAULT_MIN_GROWTH_MB_PER_HOUR
+    )]
+    pub leak_min_growth_mb_per_hour: f64,
+
+    /// Restarts of a monitored process within the window above which it is in a crash loop.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_CRASH_LOOP_RESTARTS",
+        default_value_t = restart::DEFAULT_MAX_RESTARTS
+    )]
+    pub crash_loop_restarts: usize,
+
+    /// Window, in minutes, over which restarts are counted for crash-loop detection.
+    #[arg(
+        long,
+        env = "INFRA_HEALTH_CRASH_LOOP_WINDOW_MINS",
+        default_value_t = restart::DEFAULT_WINDOW_MINS,
+        value_parser = clap::value_parser!(u64).range(1..=restart::MAX_WINDOW_MINS)
+    )]
+    pub crash_loop_window_mins: u64,
 }
 
 impl Config {
-    /// get agent ID, upon failure fallback to hostname.
-    pub fn resolved_agent_id(&self) -> String {
-        self.agent_id.clone().unwrap_or_else(|| {
-            hostname::get()
-                .map(|h| h.to_string_lossy().into_owned())
-                .unwrap_or_else(|_| "unknown-agent".to_string())
-        })
+    /// get agent ID, falling back to the persisted agent UUID.
+    pub fn resolved_agent_id(&self, identity: &AgentIdentity) -> String {
+        self.agent_id
+            .clone()
+            .unwrap_or_else(|| identity.agent_uuid.to_string())
     }
 
     pub fn collect_interval(&self) -> Duration {
         Duration::from_millis(self.collect_interval_ms)
     }
 
+    pub fn maintenance_default_duration(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.maintenance_default_mins as i64)
+    }
+
+    pub fn steal_thresholds(&self) -> StealThresholds {
+        StealThresholds {
+            degraded_pct: self.steal_degraded_pct,
+            unhealthy_pct: self.steal_unhealthy_pct,
+        }
+    }
+
+    pub fn psi_thresholds(&self) -> PsiThresholds {
+        PsiThresholds {
+            some_degraded_pct: self.psi_some_degraded_pct,
+            some_unhealthy_pct: self.psi_some_unhealthy_pct,
+            full_degraded_pct: self.psi_full_degraded_pct,
+            full_unhealthy_pct: self.psi_full_unhealthy_pct,
+        }
+    }
+
+    pub fn leak_thresholds(&self) -> LeakThresholds {
+        LeakThresholds {
+            window: Duration::from_secs(self.leak_window_mins * 60),
+            sustained: Duration::from_secs(self.leak_sustained_mins * 60),
+            min_growth_bytes_per_hour: self.leak_min_growth_mb_per_hour * 1024.0 * 1024.0,
+        }
+    }
+
+    pub fn crash_loop_thresholds(&self) -> CrashLoopThresholds {
+        CrashLoopThresholds {
+            max_restarts: self.crash_loop_restarts,
+            window: Duration::from_secs(self.crash_loop_window_mins * 60),
+        }
+    }
+
+    pub fn sched_latency_thresholds(&self) -> SchedLatencyThresholds {
+        SchedLatencyThresholds {
+            wait_degraded_ms: self.sched_wait_degraded_ms,
+            wait_unhealthy_ms: self.sched_wait_unhealthy_ms,
+        }
+    }
+
     pub fn retry_backoff(&self) -> Duration {
         Duration::from_millis(self.retry_backoff_ms)
     }
 }
+
+/// Parse a percentage between 0 and 100.
+fn parse_pct(raw: &str) -> Result<f64, String> {
+    let pct: f64 = raw
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected a percentage, got '{raw}'"))?;
+    if !(0.0..=100.0).contains(&pct) {
+        return Err(format!("percentage must be between 0 and 100, got {pct}"));
+    }
+    Ok(pct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_defaults_match_collectors() {
+        let config = Config::try_parse_from(["infra-health-agent"]).unwrap();
+        assert_eq!(config.steal_thresholds(), StealThresholds::default());
+        assert_eq!(config.psi_thresholds(), PsiThresholds::default());
+        assert_eq!(
+            config.sched_latency_thresholds(),
+            SchedLatencyThresholds::default()
+        );
+        assert_eq!(config.leak_thresholds(), LeakThresholds::default());
+        assert_eq!(
+            config.crash_loop_thresholds(),
+            CrashLoopThresholds::default()
+        );
+    }
+
+    #[test]
+    fn test_selector_list_keeps_commas_in_regex() {
+        let config = Config::try_parse_from([
+            "infra-health-agent",
+            "--monitored-processes",
+            "cmdline:mysqld.*--port=33[0-9]{1,2};name:mysqld",
+            "--monitored-processes",
+            "user:mysql",
+        ])
+        .unwrap();
+        let selectors: Vec<String> = config
+            .monitored_processes
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            selectors,
+            [
+                "cmdline:mysqld.*--port=33[0-9]{1,2}",
+                "name:mysqld",
+                "user:mysql"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leak_window_out_of_range_is_rejected() {
+        let huge = u64::MAX.to_string();
+        assert!(
+            Config::try_parse_from(["infra-health-agent", "--leak-window-mins", &huge]).is_err()
+        );
+        assert!(
+            Config::try_parse_from(["infra-health-agent", "--leak-sustained-mins", &huge]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_maintenance_default_out_of_range_is_rejected() {
+        for mins in [
+            "0",
+            &(MAX_WINDOW_MINUTES + 1).to_string(),
+            &u64::MAX.to_string(),
+        ] {
+            assert!(Config::try_parse_from([
+                "infra-health-agent",
+                "--maintenance-default-mins",
+                mins
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_steal_threshold_out_of_range_is_rejected() {
+        for pct in ["-1", "101", "NaN", "lots"] {
+            assert!(
+                Config::try_parse_from(["infra-health-agent", "--steal-degraded-pct", pct])
+                    .is_err()
+            );
+        }
+        let config =
+            Config::try_parse_from(["infra-health-agent", "--steal-unhealthy-pct", "30"]).unwrap();
+        assert_eq!(config.steal_thresholds().unhealthy_pct, 30.0);
+    }
+
+    #[test]
+    fn test_crash_loop_window_out_of_range_is_rejected() {
+        let huge = u64::MAX.to_string();
+        assert!(
+            Config::try_parse_from(["infra-health-agent", "--crash-loop-window-mins", &huge])
+                .is_err()
+        );
+    }
+}
@@ -23,3 +23,41 @@ pub enum CollectorError {
     #[error("collection timed out after {timeout_ms}ms")]
     Timeout { timeout_ms: u64 },
 }
+
+/// errors loading secrets -- never carry the secret value itself.
+#[derive(Error, Debug)]
+pub enum SecretError {
+    #[error("unsupported secret source, expected file:<path>, env:<var> or cred:<name>")]
+    UnsupportedSource,
+
+    #[error("environment variable {var} is not set")]
+    EnvNotSet { var: String },
+
+    #[error("CREDENTIALS_DIRECTORY is not set, cannot load credential {name}")]
+    NoCredentialsDirectory { name: String },
+
+    #[error("invalid credential name '{name}', expected a plain file name")]
+    InvalidCredentialName { name: String },
+
+    #[error("failed to read secret from {path}: {source}")]
+    ReadError {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("secret from {origin} is empty")]
+    Empty { origin: String },
+}
+
+/// errors loading or persisting the agent identity.
+#[derive(Error, Debug)]
+pub enum IdentityError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("identity file {path} is corrupt: {reason}")]
+    Corrupt { path: String, reason: String },
+}
@@ -0,0 +1,216 @@
+use crate::collectors::CollectionResult;
+use crate::errors::IdentityError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const IDENTITY_FILE: &str = "identity.json";
+
+/// Stable agent identity persisted in the state directory. The UUID survives
+/// host renames and restarts; the hostname is tracked alongside it so a
+/// rename shows up as a change under the same identity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentIdentity {
+    pub agent_uuid: Uuid,
+    pub hostname: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_hostname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname_changed_at: Option<DateTime<Utc>>,
+}
+
+/// A hostname change detected under the same identity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostnameChange {
+    pub from: String,
+    pub to: String,
+}
+
+impl AgentIdentity {
+    /// Load the identity from `state_dir`, creating and persisting a new one on
+    /// first start. A hostname differing from the stored one is recorded and
+    /// returned as a change; failing to persist that change only warns, since
+    /// the loaded identity is still the right one to report under.
+    pub fn load_or_create(
+        state_dir: &Path,
+        hostname: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(Self, Option<HostnameChange>), IdentityError> {
+        let path = state_dir.join(IDENTITY_FILE);
+        let mut identity = match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                serde_json::from_str::<Self>(&content).map_err(|e| IdentityError::Corrupt {
+                    path: path.display().to_string(),
+                    reason: e.to_string(),
+                })?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let identity = Self {
+                    agent_uuid: Uuid::new_v4(),
+                    hostname: hostname.to_string(),
+                    created_at: now,
+                    previous_hostname: None,
+                    hostname_changed_at: None,
+                };
+                identity.persist(state_dir)?;
+                return Ok((identity, None));
+            }
+            Err(e) => {
+                return Err(IdentityError::Io {
+                    path: path.display().to_string(),
+                    source: e,
+                })
+            }
+        };
+
+        let change = identity.observe_hostname(hostname, now);
+        if change.is_some() {
+            if let Err(e) = identity.persist(state_dir) {
+                tracing::warn!(error = %e, "failed to persist agent identity");
+            }
+        }
+        Ok((identity, change))
+    }
+
+    /// Identity for hosts without a usable state directory. Not persisted,
+    /// so it changes on every start.
+    pub fn ephemeral(hostname: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            agent_uuid: Uuid::new_v4(),
+            hostname: hostname.to_string(),
+            created_at: now,
+            previous_hostname: None,
+            hostname_changed_at: None,
+        }
+    }
+
+    /// Record `hostname` as current, returning the change if it differs.
+    pub fn observe_hostname(
+        &mut self,
+        hostname: &str,
+        now: DateTime<Utc>,
+    ) -> Option<HostnameChange> {
+        if self.hostname == hostname {
+            return None;
+        }
+        let change = HostnameChange {
+            from: std::mem::replace(&mut self.hostname, hostname.to_string()),
+            to: hostname.to_string(),
+        };
+        self.previous_hostname = Some(change.from.clone());
+        self.hostname_changed_at = Some(now);
+        Some(change)
+    }
+
+    /// Write the identity atomically (temp file + fsync + rename), so a crash
+    /// leaves either the old file or the new one, never a truncated one.
+    pub fn persist(&self, state_dir: &Path) -> Result<(), IdentityError> {
+        let io_err = |path: &Path| {
+            let path = path.display().to_string();
+            move |source| IdentityError::Io { path, source }
+        };
+        std::fs::create_dir_all(state_dir).map_err(io_err(state_dir))?;
+
+        let path: PathBuf = state_dir.join(IDENTITY_FILE);
+        let tmp = state_dir.join(format!("{IDENTITY_FILE}.tmp"));
+        let json = serde_json::to_string_pretty(self).expect("identity serializes");
+        let mut file = std::fs::File::create(&tmp).map_err(io_err(&tmp))?;
+        file.write_all(json.as_bytes()).map_err(io_err(&tmp))?;
+        file.sync_all().map_err(io_err(&tmp))?;
+        std::fs::rename(&tmp, &path).map_err(io_err(&path))
+    }
+
+    /// Attach identity fields to a result's metadata.
+    pub fn apply(&self, result: &mut CollectionResult) {
+        result
+            .metadata
+            .insert("agent_uuid".into(), self.agent_uuid.to_string());
+        result
+            .metadata
+            .insert("hostname".into(), self.hostname.clone());
+        if let (Some(prev), Some(at)) = (&self.previous_hostname, self.hostname_changed_at) {
+            result
+                .metadata
+                .insert("previous_hostname".into(), prev.clone());
+            result
+                .metadata
+                .insert("hostname_changed_at".into(), at.to_rfc3339());
+        }
+    }
+}
+
+/// Current hostname, if it can be determined.
+pub fn current_hostname() -> Option<String> {
+    hostname::get()
+        .ok()
+        .map(|h| h.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_created_then_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let (first, change) = AgentIdentity::load_or_create(dir.path(), "db-01", now).unwrap();
+        assert!(change.is_none());
+        assert!(dir.path().join(IDENTITY_FILE).exists());
+
+        let (second, change) = AgentIdentity::load_or_create(dir.path(), "db-01", now).unwrap();
+        assert!(change.is_none());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_hostname_change_detected_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let (first, _) = AgentIdentity::load_or_create(dir.path(), "db-01", now).unwrap();
+        let (renamed, change) =
+            AgentIdentity::load_or_create(dir.path(), "db-01-new", now).unwrap();
+
+        assert_eq!(renamed.agent_uuid, first.agent_uuid);
+        assert_eq!(
+            change,
+            Some(HostnameChange {
+                from: "db-01".into(),
+                to: "db-01-new".into()
+            })
+        );
+
+        let (reloaded, change) =
+            AgentIdentity::load_or_create(dir.path(), "db-01-new", now).unwrap();
+        assert!(change.is_none());
+        assert_eq!(reloaded.previous_hostname.as_deref(), Some("db-01"));
+    }
+
+    #[test]
+    fn test_hostname_change_kept_when_persist_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let (first, _) = AgentIdentity::load_or_create(dir.path(), "db-01", now).unwrap();
+        // a directory in the way of the temp file makes the write fail
+        std::fs::create_dir(dir.path().join(format!("{IDENTITY_FILE}.tmp"))).unwrap();
+
+        let (renamed, change) =
+            AgentIdentity::load_or_create(dir.path(), "db-01-new", now).unwrap();
+        assert_eq!(renamed.agent_uuid, first.agent_uuid);
+        assert_eq!(renamed.hostname, "db-01-new");
+        assert!(change.is_some());
+    }
+
+    #[test]
+    fn test_corrupt_identity_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(IDENTITY_FILE), "not json").unwrap();
+        assert!(matches!(
+            AgentIdentity::load_or_create(dir.path(), "db-01", Utc::now()),
+            Err(IdentityError::Corrupt { .. })
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::collectors::CollectionResult;
+use crate::config::Config;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::fs;
+use tokio::process::Command;
+
+/// Label values longer than this are truncated before being reported.
+pub const MAX_LABEL_VALUE_LEN: usize = 128;
+
+/// `mysqld --version` should return at once; a hung binary must not block startup.
+const MYSQLD_VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Static and dynamic labels merged into every collection result's metadata.
+///
+/// Static labels come from config (region, cluster, role, server id, ...),
+/// dynamic ones are discovered once at startup. The total is capped so a
+/// misconfiguration cannot explode series cardinality downstream.
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    labels: BTreeMap<String, String>,
+}
+
+impl Labels {
+    /// Resolve static labels from config and discover dynamic ones from the host.
+    pub async fn resolve(config: &Config) -> Self {
+        let mut dynamic = Vec::new();
+        if let Some(kernel) = kernel_version().await {
+            dynamic.push(("kernel_version".to_string(), kernel));
+        }
+        if let Some(id) = container_id().await {
+            dynamic.push(("container_id".to_string(), id));
+        }
+        if let Some(version) = mysqld_version(&config.mysqld_bin, MYSQLD_VERSION_TIMEOUT).await {
+            dynamic.push(("mysqld_version".to_string(), version));
+        }
+
+        Self::from_pairs(&config.labels, &dynamic, config.max_labels)
+    }
+
+    /// Build the label set, static labels first, then dynamic ones, dropping
+    /// anything beyond `max_labels`. Static labels win on key collisions.
+    pub fn from_pairs(
+        static_labels: &[(String, String)],
+        dynamic_labels: &[(String, String)],
+        max_labels: usize,
+    ) -> Self {
+        let mut labels = BTreeMap::new();
+        for (key, value) in static_labels.iter().chain(dynamic_labels) {
+            if labels.contains_key(key) {
+                continue;
+            }
+            if labels.len() >= max_labels {
+                tracing::warn!(label = %key, max_labels, "label cardinality cap reached, dropping label");
+                continue;
+            }
+            let value: String = value.chars().take(MAX_LABEL_VALUE_LEN).collect();
+            labels.insert(key.clone(), value);
+        }
+        Self { labels }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.labels.get(key).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Merge labels into a result's metadata. Keys set by the collector itself are kept.
+    pub fn apply(&self, result: &mut CollectionResult) {
+        for (key, value) in &self.labels {
+            result
+                .metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+}
+
+/// Parse a `key=value` label from the command line or environment.
+pub fn parse_label(raw: &str) -> Result<(String, String), String> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{raw}'"))?;
+    let key = key.trim();
+    let valid_key = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_key {
+        return Err(format!("invalid label key '{key}'"));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+async fn kernel_version() -> Option<String> {
+    let release = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .await
+        .ok()?;
+    let release = release.trim();
+    (!release.is_empty()).then(|| release.to_string())
+}
+
+async fn container_id() -> Option<String> {
+    let content = fs::read_to_string("/proc/self/cgroup").await.ok()?;
+    parse_container_id(&content)
+}
+
+/// Find a 64-hex-digit container id in /proc/self/cgroup, as written by
+/// docker, containerd and podman (`/docker/<id>`, `docker-<id>.scope`, ...).
+fn parse_container_id(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        path.split(['/', '-', '.'])
+            .find(|part| part.len() == 64 && part.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(str::to_string)
+    })
+}
+
+async fn mysqld_version(bin: &str, timeout: Duration) -> Option<String> {
+    let output = Command::new(bin)
+        .arg("--version")
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(timeout, output).await {
+        Ok(output) => output.ok()?,
+        Err(_) => {
+            tracing::warn!(
+                bin,
+                "mysqld --version timed out, skipping mysqld_version label"
+            );
+            return None;
+        }
+    };
+    if !output.status.success() {
+        return None;
+    }
+    parse_mysqld_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extract the version from `mysqld --version` output,
+/// e.g. "/usr/sbin/mysqld  Ver 8.0.36 for Linux on x86_64 (MySQL Community Server - GPL)".
+fn parse_mysqld_version(output: &str) -> Option<String> {
+    let mut parts = output.split_whitespace();
+    parts.find(|p| *p == "Ver")?;
+    parts.next().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::{CheckStatus, MemorySnapshot, MetricPayload};
+    use std::collections::HashMap;
+
+    fn pairs(raw: &[(&str, &str)]) -> Vec<(String, String)> {
+        raw.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_label("region=westeurope").unwrap(),
+            ("region".to_string(), "westeurope".to_string())
+        );
+        assert!(parse_label("region").is_err());
+        assert!(parse_label("1bad=x").is_err());
+        assert!(parse_label("bad-key=x").is_err());
+    }
+
+    #[test]
+    fn test_cardinality_cap_prefers_static_labels() {
+        let labels = Labels::from_pairs(
+            &pairs(&[("region", "westeurope"), ("role", "primary")]),
+            &pairs(&[("kernel_version", "6.1.0"), ("role", "replica")]),
+            2,
+        );
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels.get("role"), Some("primary"));
+        assert_eq!(labels.get("kernel_version"), None);
+    }
+
+    #[test]
+    fn test_long_values_truncated() {
+        let long = "x".repeat(MAX_LABEL_VALUE_LEN * 2);
+        let labels = Labels::from_pairs(&[("cluster".to_string(), long)], &[], 8);
+        assert_eq!(labels.get("cluster").unwrap().len(), MAX_LABEL_VALUE_LEN);
+    }
+
+    #[test]
+    fn test_apply_keeps_collector_metadata() {
+        let labels =
+            Labels::from_pairs(&pairs(&[("region", "westeurope"), ("source", "x")]), &[], 8);
+        let mut result = CollectionResult {
+            check_name: "memory".into(),
+            status: CheckStatus::Healthy,
+            message: String::new(),
+            metadata: HashMap::from([("source".to_string(), "collector".to_string())]),
+            latency_us: 0,
+            in_maintenance: false,
+            payload: MetricPayload::Memory(MemorySnapshot {
+                total_bytes: 0,
+                available_bytes: 0,
+                used_bytes: 0,
+                swap_total_bytes: 0,
+                swap_used_bytes: 0,
+                memory_pressure_pct: 0.0,
+                ..MemorySnapshot::default()
+            }),
+        };
+        labels.apply(&mut result);
+        assert_eq!(result.metadata["region"], "westeurope");
+        assert_eq!(result.metadata["source"], "collector");
+    }
+
+    #[test]
+    fn test_parse_container_id() {
+        let id = "3f4e1b2c".repeat(8);
+        let v1 = format!("12:memory:/docker/{id}\n0::/");
+        assert_eq!(parse_container_id(&v1), Some(id.clone()));
+        let v2 = format!("0::/system.slice/docker-{id}.scope");
+        assert_eq!(parse_container_id(&v2), Some(id));
+        assert_eq!(parse_container_id("0::/user.slice"), None);
+    }
+
+    #[tokio::test]
+    async fn test_mysqld_version_times_out() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("mysqld");
+        std::fs::write(&bin, "#!/bin/sh\nsleep 10\n").unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let started = std::time::Instant::now();
+        let version = mysqld_version(bin.to_str().unwrap(), Duration::from_millis(100)).await;
+        assert_eq!(version, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_parse_mysqld_version() {
+        let out = "/usr/sbin/mysqld  Ver 8.0.36 for Linux on x86_64 (MySQL Community Server - GPL)";
+        assert_eq!(parse_mysqld_version(out), Some("8.0.36".to_string()));
+        assert_eq!(parse_mysqld_version("garbage"), None);
+    }
+}
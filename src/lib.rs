@@ -2,3 +2,8 @@ pub mod collectors;
 pub mod config;
 pub mod daemon;
 pub mod errors;
+pub mod identity;
+pub mod labels;
+pub mod maintenance;
+pub mod secrets;
+pub mod selectors;
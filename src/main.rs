@@ -1,32 +1,189 @@
+use clap::Parser;
+use infra_health_agent::collectors::cgroup_cpu::CgroupCpuCollector;
+use infra_health_agent::collectors::cgroup_memory::CgroupMemoryCollector;
 use infra_health_agent::collectors::cpu::CpuCollector;
+use infra_health_agent::collectors::cpufreq::CpuFreqCollector;
+use infra_health_agent::collectors::interrupts::InterruptsCollector;
+use infra_health_agent::collectors::leak::LeakCollector;
 use infra_health_agent::collectors::memory::MemoryCollector;
-use infra_health_agent::collectors::Collector;
-use infra_health_agent::errors::CollectorError;
+use infra_health_agent::collectors::numa::NumaCollector;
+use infra_health_agent::collectors::oom::OomCollector;
+use infra_health_agent::collectors::overcommit::OvercommitCollector;
+use infra_health_agent::collectors::process::ProcessCollector;
+use infra_health_agent::collectors::psi::PsiCollector;
+use infra_health_agent::collectors::restart::RestartCollector;
+use infra_health_agent::collectors::schedstat::SchedstatCollector;
+use infra_health_agent::collectors::selection::ProcessSelectionCollector;
+use infra_health_agent::collectors::slab::SlabCollector;
+use infra_health_agent::collectors::vmstat::VmstatCollector;
+use infra_health_agent::collectors::{timed_collect, Collector};
+use infra_health_agent::config::Config;
+use infra_health_agent::errors::IdentityError;
+use infra_health_agent::identity::{current_hostname, AgentIdentity};
+use infra_health_agent::labels::Labels;
+use infra_health_agent::maintenance::MaintenanceMode;
+use infra_health_agent::selectors::MonitoredProcesses;
+use tokio::signal::unix::{signal, SignalKind};
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Test memory collector
-    let mut mem = MemoryCollector::new();
-    match mem.collect().await {
-        Ok(result) => println!("Memory: {:?}\n", result),
-        Err(e) => eprintln!("Memory error: {}", e),
+    let config = Config::parse();
+    init_tracing(&config);
+
+    let labels = Labels::resolve(&config).await;
+    let (mut identity, persistent) = load_identity(&config)?;
+
+    let mut collectors: Vec<Box<dyn Collector>> = vec![
+        Box::new(CpuCollector::new(
+            &config.proc_root,
+            config.steal_thresholds(),
+        )),
+        Box::new(MemoryCollector::new(&config.proc_root)),
+        Box::new(CpuFreqCollector::new(&config.sys_root)),
+        Box::new(PsiCollector::new(
+            &config.proc_root,
+            config.psi_thresholds(),
+        )),
+        Box::new(CgroupCpuCollector::new(
+            &config.sys_root,
+            config.cgroup.clone(),
+        )),
+        Box::new(CgroupMemoryCollector::new(
+            &config.sys_root,
+            config.cgroup.clone(),
+        )),
+        Box::new(InterruptsCollector::new(&config.proc_root)),
+        Box::new(SchedstatCollector::new(
+            &config.proc_root,
+            config.sched_latency_thresholds(),
+        )),
+        Box::new(VmstatCollector::new(&config.proc_root)),
+        Box::new(OomCollector::new(&config.proc_root, &config.kmsg_path)),
+        Box::new(OvercommitCollector::new(&config.proc_root)),
+        Box::new(NumaCollector::new(&config.sys_root)),
+        Box::new(SlabCollector::new(&config.proc_root)),
+    ];
+    // selectors are resolved once per cycle and shared by the process collectors
+    let monitored = MonitoredProcesses::new(config.monitored_processes.clone(), &config.proc_root);
+    if !monitored.is_empty() {
+        collectors.push(Box::new(ProcessSelectionCollector::new(monitored.clone())));
+        collectors.push(Box::new(ProcessCollector::new(monitored.clone())));
+        collectors.push(Box::new(RestartCollector::new(
+            monitored.clone(),
+            config.crash_loop_thresholds(),
+        )));
+        collectors.push(Box::new(LeakCollector::new(
+            monitored.clone(),
+            config.leak_thresholds(),
+        )));
     }
 
-    // Test CPU collector - needs two samples
-    let mut cpu = CpuCollector::new();
+    let maintenance = MaintenanceMode::new(
+        config.maintenance_windows.clone(),
+        config.maintenance_file.clone(),
+        config.maintenance_default_duration(),
+    );
+    spawn_maintenance_signals(maintenance.clone())?;
 
-    // First call seeds the baseline
-    let _ = cpu.collect().await;
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    tracing::info!(
+        agent_id = %config.resolved_agent_id(&identity),
+        hostname = %identity.hostname,
+        "starting infra health agent"
+    );
+
+    let mut interval = tokio::time::interval(config.collect_interval());
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now();
+        let window = maintenance.active_window(now).await;
+        if !monitored.is_empty() {
+            monitored.refresh().await;
+        }
+        if let Some(hostname) = current_hostname() {
+            if let Some(change) = identity.observe_hostname(&hostname, now) {
+                tracing::warn!(from = %change.from, to = %change.to, "hostname changed under the same agent identity");
+                // an ephemeral identity must never replace the stored one
+                if persistent {
+                    if let Err(e) = identity.persist(&config.state_dir) {
+                        tracing::warn!(error = %e, "failed to persist agent identity");
+                    }
+                }
+            }
+        }
+        for collector in collectors.iter_mut() {
+            match timed_collect(collector.as_mut()).await {
+                Ok(mut result) => {
+                    labels.apply(&mut result);
+                    identity.apply(&mut result);
+                    if let Some(window) = &window {
+                        result.mark_maintenance(window);
+                    }
+                    if result.is_actionable() {
+                        tracing::warn!(check = %result.check_name, status = ?result.status, "{}", result.message);
+                    }
+                    // TODO:: hand off to a report sink
+                    println!("{}", serde_json::to_string(&result)?);
+                }
+                Err(e) => {
+                    tracing::warn!(collector = collector.name(), error = %e, "collection failed")
+                }
+            }
+        }
+    }
+}
 
-    // call #2
-    match cpu.collect().await {
-        Ok(result) => println!("CPU: {:?}\n", result),
-        Err(CollectorError::ParseError { field, path, .. }) => {
-            eprintln!("Failed to parse {} in {}", field, path);
+/// Load or create the persisted identity. A corrupt identity file stops the
+/// agent rather than being replaced, since that would change its UUID; an
+/// unusable state directory falls back to an ephemeral identity, flagged by
+/// the returned bool so it is never persisted over the stored one.
+fn load_identity(config: &Config) -> anyhow::Result<(AgentIdentity, bool)> {
+    let hostname = current_hostname().unwrap_or_else(|| "unknown-host".to_string());
+    let now = chrono::Utc::now();
+    match AgentIdentity::load_or_create(&config.state_dir, &hostname, now) {
+        Ok((identity, change)) => {
+            if let Some(change) = change {
+                tracing::warn!(from = %change.from, to = %change.to, "hostname changed under the same agent identity");
+            }
+            Ok((identity, true))
+        }
+        Err(e @ IdentityError::Corrupt { .. }) => Err(e.into()),
+        Err(e) => {
+            tracing::error!(error = %e, "cannot load persistent identity, using an ephemeral one");
+            Ok((AgentIdentity::ephemeral(&hostname, now), false))
         }
-        Err(e) => eprintln!("Other error: {}", e),
     }
+}
 
+/// SIGUSR1 opens a maintenance window of the default length, SIGUSR2 closes it.
+fn spawn_maintenance_signals(maintenance: MaintenanceMode) -> std::io::Result<()> {
+    let mut start = signal(SignalKind::user_defined1())?;
+    let mut end = signal(SignalKind::user_defined2())?;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = start.recv() => {
+                    let until = maintenance.start_manual(chrono::Utc::now());
+                    tracing::info!(%until, "maintenance window started by SIGUSR1");
+                }
+                _ = end.recv() => {
+                    maintenance.end_manual();
+                    tracing::info!("maintenance window ended by SIGUSR2");
+                }
+            }
+        }
+    });
     Ok(())
 }
+
+fn init_tracing(config: &Config) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    if config.json_logs {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
@@ -0,0 +1,381 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Longest window a schedule may declare; windows always end on their own.
+pub const MAX_WINDOW_MINUTES: i64 = 24 * 60;
+
+/// An active maintenance window and what opened it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceWindow {
+    pub source: String,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// A recurring window: a cron expression (minute hour day-of-month month
+/// day-of-week, evaluated in UTC) for the start, plus a duration,
+/// e.g. `0 2 * * 0 90m` for Sundays 02:00-03:30.
+#[derive(Debug, Clone)]
+pub struct MaintenanceSchedule {
+    raw: String,
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    duration: Duration,
+}
+
+/// Parse a schedule from the command line or environment.
+pub fn parse_schedule(raw: &str) -> Result<MaintenanceSchedule, String> {
+    let fields: Vec<&str> = raw.split_whitespace().collect();
+    if fields.len() != 6 {
+        return Err(format!(
+            "expected 5 cron fields and a duration (e.g. '0 2 * * 0 90m'), got '{raw}'"
+        ));
+    }
+
+    let mut days_of_week = parse_cron_field(fields[4], 0, 7, "day-of-week")?;
+    // 7 is an alias for Sunday
+    if days_of_week[7] {
+        days_of_week[0] = true;
+    }
+    days_of_week.truncate(7);
+
+    let duration = parse_duration(fields[5])?;
+    if duration <= Duration::zero() || duration > Duration::minutes(MAX_WINDOW_MINUTES) {
+        return Err(format!(
+            "window duration must be between 1m and {MAX_WINDOW_MINUTES}m, got '{}'",
+            fields[5]
+        ));
+    }
+
+    Ok(MaintenanceSchedule {
+        raw: raw.split_whitespace().collect::<Vec<_>>().join(" "),
+        minutes: parse_cron_field(fields[0], 0, 59, "minute")?,
+        hours: parse_cron_field(fields[1], 0, 23, "hour")?,
+        days_of_month: parse_cron_field(fields[2], 1, 31, "day-of-month")?,
+        months: parse_cron_field(fields[3], 1, 12, "month")?,
+        days_of_week,
+        duration,
+    })
+}
+
+/// Parse one cron field into a membership table indexed by value.
+/// Supports `*`, `n`, `a-b`, `a,b,c` and `/step` on `*` or ranges.
+fn parse_cron_field(field: &str, min: u32, max: u32, name: &str) -> Result<Vec<bool>, String> {
+    let err = || format!("invalid {name} field '{field}'");
+    let mut table = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| err())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(err());
+        }
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (
+                lo.parse().map_err(|_| err())?,
+                hi.parse().map_err(|_| err())?,
+            )
+        } else {
+            let v: u32 = range.parse().map_err(|_| err())?;
+            (v, if part.contains('/') { max } else { v })
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(err());
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            table[v as usize] = true;
+        }
+    }
+    Ok(table)
+}
+
+/// `90m`, `2h`, or a bare number of minutes.
+fn parse_duration(raw: &str) -> Result<Duration, String> {
+    let err = || format!("invalid duration '{raw}', expected e.g. 30m or 2h");
+    let (num, unit) = match raw.strip_suffix('h') {
+        Some(n) => (n, 60),
+        None => (raw.strip_suffix('m').unwrap_or(raw), 1),
+    };
+    let n: i64 = num.parse().map_err(|_| err())?;
+    n.checked_mul(unit)
+        .and_then(Duration::try_minutes)
+        .ok_or_else(err)
+}
+
+impl MaintenanceSchedule {
+    fn starts_at(&self, t: DateTime<Utc>) -> bool {
+        let dom_restricted = self.days_of_month.iter().skip(1).any(|d| !d);
+        let dow_restricted = self.days_of_week.iter().any(|d| !d);
+        let dom = self.days_of_month[t.day() as usize];
+        let dow = self.days_of_week[t.weekday().num_days_from_sunday() as usize];
+        // classic cron: when both day fields are restricted, either may match
+        let day_ok = if dom_restricted && dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        };
+        self.minutes[t.minute() as usize]
+            && self.hours[t.hour() as usize]
+            && self.months[t.month() as usize]
+            && day_ok
+    }
+
+    /// The window covering `now`, if any: the latest matching start within
+    /// one duration before `now`.
+    pub fn active_window(&self, now: DateTime<Utc>) -> Option<MaintenanceWindow> {
+        let minute_floor = Utc
+            .with_ymd_and_hms(
+                now.year(),
+                now.month(),
+                now.day(),
+                now.hour(),
+                now.minute(),
+                0,
+            )
+            .single()?;
+        (0..self.duration.num_minutes())
+            .map(|back| minute_floor - Duration::minutes(back))
+            .find(|start| self.starts_at(*start))
+            .map(|start| MaintenanceWindow {
+                source: format!("schedule:{}", self.raw),
+                ends_at: start + self.duration,
+            })
+    }
+}
+
+/// Decides whether the agent is currently inside a maintenance window.
+/// Windows come from config schedules, a marker file, or a runtime command
+/// (see [`MaintenanceMode::start_manual`]), and all of them expire on their own.
+#[derive(Debug, Clone)]
+pub struct MaintenanceMode {
+    schedules: Vec<MaintenanceSchedule>,
+    marker_file: Option<PathBuf>,
+    default_duration: Duration,
+    manual_until: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// End of the expired marker window already warned about, so a stale
+    /// marker is reported once rather than every cycle.
+    expired_marker_warned: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+impl MaintenanceMode {
+    pub fn new(
+        schedules: Vec<MaintenanceSchedule>,
+        marker_file: Option<PathBuf>,
+        default_duration: Duration,
+    ) -> Self {
+        Self {
+            schedules,
+            marker_file,
+            default_duration,
+            manual_until: Arc::new(Mutex::new(None)),
+            expired_marker_warned: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Open a manual window lasting the default duration from `now`, at
+    /// most [`MAX_WINDOW_MINUTES`].
+    pub fn start_manual(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let until = now
+            + self
+                .default_duration
+                .min(Duration::minutes(MAX_WINDOW_MINUTES));
+        *self.manual_until.lock().unwrap() = Some(until);
+        until
+    }
+
+    /// Close a manual window early.
+    pub fn end_manual(&self) {
+        *self.manual_until.lock().unwrap() = None;
+    }
+
+    /// The currently active window, if any. Manual windows take precedence,
+    /// then the marker file, then schedules.
+    pub async fn active_window(&self, now: DateTime<Utc>) -> Option<MaintenanceWindow> {
+        if let Some(until) = *self.manual_until.lock().unwrap() {
+            if now < until {
+                return Some(MaintenanceWindow {
+                    source: "manual".into(),
+                    ends_at: until,
+                });
+            }
+        }
+
+        if let Some(path) = &self.marker_file {
+            if let Some(window) = self.marker_window(path, now).await {
+                return Some(window);
+            }
+        }
+
+        self.schedules.iter().find_map(|s| s.active_window(now))
+    }
+
+    /// A marker file opens a window while it exists. If it contains an RFC 3339
+    /// timestamp that is the end of the window; otherwise the window ends the
+    /// default duration after the file was last modified. Either way it ends
+    /// at most [`MAX_WINDOW_MINUTES`] after the last modification, so a
+    /// forgotten marker cannot mute a host forever.
+    async fn marker_window(
+        &self,
+        path: &std::path::Path,
+        now: DateTime<Utc>,
+    ) -> Option<MaintenanceWindow> {
+        let meta = tokio::fs::metadata(path).await.ok()?;
+        let content = tokio::fs::read_to_string(path).await.unwrap_or_default();
+        let modified = DateTime::<Utc>::from(meta.modified().ok()?);
+        let latest_end = modified + Duration::minutes(MAX_WINDOW_MINUTES);
+        let ends_at = match DateTime::parse_from_rfc3339(content.trim()) {
+            Ok(t) => t.with_timezone(&Utc).min(latest_end),
+            Err(_) => (modified + self.default_duration).min(latest_end),
+        };
+        if now >= ends_at {
+            let mut warned = self.expired_marker_warned.lock().unwrap();
+            if *warned != Some(ends_at) {
+                tracing::warn!(path = %path.display(), %ends_at, "maintenance marker file has expired");
+                *warned = Some(ends_at);
+            }
+            return None;
+        }
+        Some(MaintenanceWindow {
+            source: format!("file:{}", path.display()),
+            ends_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_schedule_rejects_bad_input() {
+        assert!(parse_schedule("0 2 * * 0").is_err());
+        assert!(parse_schedule("60 2 * * 0 30m").is_err());
+        assert!(parse_schedule("0 2 * * 0 0m").is_err());
+        assert!(parse_schedule("0 2 * * 0 25h").is_err());
+        assert!(parse_schedule("*/0 2 * * 0 30m").is_err());
+        assert!(parse_schedule("0 2 * * 0 30x").is_err());
+        assert!(parse_schedule("0 2 * * 0 9223372036854775807h").is_err());
+        assert!(parse_schedule("0 2 * * 0 9223372036854775807m").is_err());
+    }
+
+    #[test]
+    fn test_cron_field_forms() {
+        let t = parse_cron_field("1-10/3,20,*/30", 0, 59, "minute").unwrap();
+        let set: Vec<usize> = (0..60).filter(|&i| t[i]).collect();
+        assert_eq!(set, vec![0, 1, 4, 7, 10, 20, 30]);
+    }
+
+    #[test]
+    fn test_weekly_window() {
+        // 2026-10-18 is a Sunday
+        let s = parse_schedule("0 2 * * 0 90m").unwrap();
+        assert!(s.active_window(at("2026-10-18T01:59:59Z")).is_none());
+        let w = s.active_window(at("2026-10-18T02:00:00Z")).unwrap();
+        assert_eq!(w.ends_at, at("2026-10-18T03:30:00Z"));
+        assert!(s.active_window(at("2026-10-18T03:29:00Z")).is_some());
+        assert!(s.active_window(at("2026-10-18T03:30:00Z")).is_none());
+        assert!(s.active_window(at("2026-10-19T02:10:00Z")).is_none());
+    }
+
+    #[test]
+    fn test_sunday_alias_and_day_of_month_or() {
+        let s = parse_schedule("0 2 * * 7 1h").unwrap();
+        assert!(s.active_window(at("2026-10-18T02:30:00Z")).is_some());
+        // both day fields restricted: 1st of the month OR Sunday
+        let s = parse_schedule("0 2 1 * 0 1h").unwrap();
+        assert!(s.active_window(at("2026-10-18T02:30:00Z")).is_some());
+        assert!(s.active_window(at("2026-10-01T02:30:00Z")).is_some());
+        assert!(s.active_window(at("2026-10-02T02:30:00Z")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_manual_window_expires() {
+        let mode = MaintenanceMode::new(vec![], None, Duration::minutes(30));
+        let now = at("2026-10-18T10:00:00Z");
+        mode.start_manual(now);
+        let w = mode
+            .active_window(now + Duration::minutes(29))
+            .await
+            .unwrap();
+        assert_eq!(w.source, "manual");
+        assert!(mode
+            .active_window(now + Duration::minutes(30))
+            .await
+            .is_none());
+
+        mode.start_manual(now);
+        mode.end_manual();
+        assert!(mode.active_window(now).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_default_duration_is_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("maintenance");
+        std::fs::write(&marker, "").unwrap();
+        let mode = MaintenanceMode::new(vec![], Some(marker), Duration::days(30));
+        let now = Utc::now();
+        let w = mode.active_window(now).await.unwrap();
+        assert!(w.ends_at <= now + Duration::minutes(MAX_WINDOW_MINUTES));
+
+        let until = mode.start_manual(now);
+        assert_eq!(until, now + Duration::minutes(MAX_WINDOW_MINUTES));
+    }
+
+    #[tokio::test]
+    async fn test_marker_file_with_end_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("maintenance");
+        let mode = MaintenanceMode::new(vec![], Some(marker.clone()), Duration::minutes(60));
+        let now = at("2026-10-18T10:00:00Z");
+        assert!(mode.active_window(now).await.is_none());
+
+        std::fs::write(&marker, "2026-10-18T11:00:00Z\n").unwrap();
+        let w = mode.active_window(now).await.unwrap();
+        assert_eq!(w.ends_at, at("2026-10-18T11:00:00Z"));
+        assert!(mode
+            .active_window(at("2026-10-18T11:00:00Z"))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_empty_marker_file_expires_after_default_duration() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("maintenance");
+        std::fs::write(&marker, "").unwrap();
+        let mode = MaintenanceMode::new(vec![], Some(marker), Duration::minutes(60));
+        let now = Utc::now();
+        assert!(mode.active_window(now).await.is_some());
+        assert!(mode
+            .active_window(now + Duration::minutes(61))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_marker_end_time_is_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("maintenance");
+        std::fs::write(&marker, "2099-01-01T00:00:00Z\n").unwrap();
+        let mode = MaintenanceMode::new(vec![], Some(marker), Duration::minutes(60));
+        let now = Utc::now();
+        let w = mode.active_window(now).await.unwrap();
+        assert!(w.ends_at <= now + Duration::minutes(MAX_WINDOW_MINUTES));
+        assert!(mode
+            .active_window(now + Duration::minutes(MAX_WINDOW_MINUTES + 1))
+            .await
+            .is_none());
+    }
+}
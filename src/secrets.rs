@@ -0,0 +1,224 @@
+use crate::errors::SecretError;
+use clap::builder::TypedValueParser;
+use clap::error::ErrorKind;
+use clap::{Arg, Command};
+use serde::{Serialize, Serializer};
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use zeroize::Zeroizing;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A credential loaded from a file, an environment variable or a systemd
+/// credentials directory. The value is wiped on drop and never shows up in
+/// `Debug`, `Display` or serialized output; use [`Secret::expose`] to read it.
+#[derive(Clone)]
+pub struct Secret {
+    value: Zeroizing<String>,
+    origin: String,
+}
+
+impl Secret {
+    pub fn new(value: impl Into<String>, origin: impl Into<String>) -> Self {
+        Self {
+            value: Zeroizing::new(value.into()),
+            origin: origin.into(),
+        }
+    }
+
+    /// Load a secret from a source spec:
+    /// `file:<path>`, `env:<var>` or `cred:<name>` (relative to `$CREDENTIALS_DIRECTORY`,
+    /// as populated by systemd `LoadCredential=`).
+    pub fn load(spec: &str) -> Result<Self, SecretError> {
+        let (kind, target) = spec.split_once(':').ok_or(SecretError::UnsupportedSource)?;
+        match kind {
+            "file" => Self::from_file(Path::new(target)),
+            "env" => Self::from_env(target),
+            "cred" => {
+                let dir = std::env::var_os("CREDENTIALS_DIRECTORY").ok_or_else(|| {
+                    SecretError::NoCredentialsDirectory {
+                        name: target.to_string(),
+                    }
+                })?;
+                Self::from_credentials_dir(Path::new(&dir), target)
+            }
+            _ => Err(SecretError::UnsupportedSource),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, SecretError> {
+        let content = std::fs::read_to_string(path).map_err(|e| SecretError::ReadError {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        let raw = Zeroizing::new(content);
+        Self::non_empty(
+            raw.trim_end_matches(['\r', '\n']),
+            format!("file:{}", path.display()),
+        )
+    }
+
+    pub fn from_env(var: &str) -> Result<Self, SecretError> {
+        let raw = Zeroizing::new(std::env::var(var).map_err(|_| SecretError::EnvNotSet {
+            var: var.to_string(),
+        })?);
+        Self::non_empty(&raw, format!("env:{var}"))
+    }
+
+    /// `name` must be a plain file name: separators, `.` and `..` would
+    /// reach outside the credentials directory.
+    pub fn from_credentials_dir(dir: &Path, name: &str) -> Result<Self, SecretError> {
+        let mut components = Path::new(name).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) || name.contains('/')
+        {
+            return Err(SecretError::InvalidCredentialName {
+                name: name.to_string(),
+            });
+        }
+        let path: PathBuf = dir.join(name);
+        let mut secret = Self::from_file(&path)?;
+        secret.origin = format!("cred:{name}");
+        Ok(secret)
+    }
+
+    fn non_empty(value: &str, origin: String) -> Result<Self, SecretError> {
+        if value.is_empty() {
+            return Err(SecretError::Empty { origin });
+        }
+        Ok(Self::new(value, origin))
+    }
+
+    /// The secret value. Keep the borrow short and never log it.
+    pub fn expose(&self) -> &str {
+        &self.value
+    }
+
+    /// Where the secret came from, safe to log.
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED}, origin={})", self.origin)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+/// clap value parser: load the secret named by a source spec.
+///
+/// Unlike a plain `fn` parser, errors do not echo the raw argument, which
+/// may be a password someone pasted in place of a source spec.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SecretValueParser;
+
+impl TypedValueParser for SecretValueParser {
+    type Value = Secret;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let spec = value.to_str().ok_or(SecretError::UnsupportedSource);
+        spec.and_then(Secret::load).map_err(|e| {
+            let arg = arg.map(|a| a.to_string()).unwrap_or_default();
+            clap::Error::raw(ErrorKind::ValueValidation, format!("{arg}: {e}\n"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_debug_and_display_redact() {
+        let secret = Secret::new("hunter2", "env:TEST");
+        let dbg = format!("{:?}", secret);
+        assert!(!dbg.contains("hunter2"));
+        assert!(dbg.contains("env:TEST"));
+        assert_eq!(secret.to_string(), REDACTED);
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"[REDACTED]\"");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn test_load_from_file_trims_newline() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "s3cret").unwrap();
+        let secret = Secret::load(&format!("file:{}", file.path().display())).unwrap();
+        assert_eq!(secret.expose(), "s3cret");
+    }
+
+    #[test]
+    fn test_load_from_credentials_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mysql-password"), "pw").unwrap();
+        let secret = Secret::from_credentials_dir(dir.path(), "mysql-password").unwrap();
+        assert_eq!(secret.expose(), "pw");
+        assert_eq!(secret.origin(), "cred:mysql-password");
+    }
+
+    #[test]
+    fn test_credential_name_cannot_escape_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("creds")).unwrap();
+        std::fs::write(dir.path().join("outside"), "pw").unwrap();
+        let creds = dir.path().join("creds");
+        for name in ["../outside", "/etc/passwd", "..", ".", "", "sub/name"] {
+            assert!(
+                matches!(
+                    Secret::from_credentials_dir(&creds, name),
+                    Err(SecretError::InvalidCredentialName { .. })
+                ),
+                "{name} accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_file_rejected() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(matches!(
+            Secret::from_file(file.path()),
+            Err(SecretError::Empty { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_source_does_not_echo_value() {
+        let cmd = Command::new("test");
+        let err = SecretValueParser
+            .parse_ref(&cmd, None, OsStr::new("hunter2"))
+            .unwrap_err();
+        assert!(!err.to_string().contains("hunter2"));
+        assert!(Secret::load("vault:hunter2").is_err());
+    }
+
+    #[test]
+    fn test_missing_env_var() {
+        assert!(matches!(
+            Secret::from_env("INFRA_HEALTH_TEST_SECRET_THAT_IS_NOT_SET"),
+            Err(SecretError::EnvNotSet { .. })
+        ));
+    }
+}
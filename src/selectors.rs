@@ -0,0 +1,429 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Describes which processes to monitor. PIDs change on every restart, so
+/// selectors are re-resolved against /proc on every collection cycle.
+#[derive(Debug, Clone)]
+pub enum ProcessSelector {
+    /// Exact PID, mostly useful for debugging.
+    Pid(u32),
+    /// Process name as shown in /proc/<pid>/comm.
+    Name(String),
+    /// Absolute path of the executable (/proc/<pid>/exe).
+    Exe(PathBuf),
+    /// Regex matched against the space-joined command line.
+    Cmdline(Regex),
+    /// File holding the PID, e.g. /var/run/mysqld/mysqld.pid.
+    Pidfile(PathBuf),
+    /// Owning user, by name or numeric uid (real uid). Names are looked up in
+    /// the agent's own user database, not the host's: in a container with
+    /// HOST_PROC set, use a numeric uid unless the name maps to the same uid
+    /// in both.
+    User(String),
+    /// cgroup path; matches processes in that cgroup or below it.
+    Cgroup(String),
+}
+
+/// Which PIDs a selector matched during one resolution pass.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SelectorMatch {
+    pub selector: String,
+    pub pids: Vec<u32>,
+}
+
+/// The monitored processes as resolved for the current cycle. Every
+/// collector that looks at them holds a clone, so /proc is scanned once per
+/// cycle and they all agree on which PIDs are monitored.
+#[derive(Debug, Clone)]
+pub struct MonitoredProcesses {
+    selectors: Arc<[ProcessSelector]>,
+    proc_root: PathBuf,
+    matches: Arc<RwLock<Vec<SelectorMatch>>>,
+}
+
+impl MonitoredProcesses {
+    pub fn new(selectors: Vec<ProcessSelector>, proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            selectors: selectors.into(),
+            proc_root: proc_root.into(),
+            matches: Arc::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty()
+    }
+
+    pub fn proc_root(&self) -> &Path {
+        &self.proc_root
+    }
+
+    /// Re-resolve the selectors; call once at the start of every cycle.
+    /// The previous resolution is kept if the scan cannot run.
+    pub async fn refresh(&self) {
+        let selectors = self.selectors.clone();
+        let proc_root = self.proc_root.clone();
+        match tokio::task::spawn_blocking(move || resolve(&selectors, &proc_root)).await {
+            Ok(matches) => {
+                *self.matches.write().unwrap_or_else(PoisonError::into_inner) = matches;
+            }
+            Err(e) => tracing::warn!(error = %e, "failed to resolve process selectors"),
+        }
+    }
+
+    /// Which PIDs each selector matched in the latest resolution.
+    pub fn matches(&self) -> Vec<SelectorMatch> {
+        self.matches
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Every matched PID, once.
+    pub fn pids(&self) -> BTreeSet<u32> {
+        self.matches
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .flat_map(|m| m.pids.iter().copied())
+            .collect()
+    }
+}
+
+impl fmt::Display for ProcessSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pid(pid) => write!(f, "pid:{pid}"),
+            Self::Name(name) => write!(f, "name:{name}"),
+            Self::Exe(path) => write!(f, "exe:{}", path.display()),
+            Self::Cmdline(re) => write!(f, "cmdline:{}", re.as_str()),
+            Self::Pidfile(path) => write!(f, "pidfile:{}", path.display()),
+            Self::User(user) => write!(f, "user:{user}"),
+            Self::Cgroup(path) => write!(f, "cgroup:{path}"),
+        }
+    }
+}
+
+/// Parse a `kind:value` selector from the command line or environment,
+/// e.g. `name:mysqld`, `pidfile:/var/run/mysqld/mysqld.pid`, `cmdline:mysqld.*--port=3307`.
+pub fn parse_selector(raw: &str) -> Result<ProcessSelector, String> {
+    let (kind, value) = raw
+        .split_once(':')
+        .ok_or_else(|| format!("expected kind:value, got '{raw}'"))?;
+    if value.is_empty() {
+        return Err(format!("empty value in selector '{raw}'"));
+    }
+    match kind {
+        "pid" => value
+            .parse()
+            .map(ProcessSelector::Pid)
+            .map_err(|_| format!("invalid pid '{value}'")),
+        "name" => Ok(ProcessSelector::Name(value.to_string())),
+        "exe" => Ok(ProcessSelector::Exe(PathBuf::from(value))),
+        "cmdline" => Regex::new(value)
+            .map(ProcessSelector::Cmdline)
+            .map_err(|e| format!("invalid cmdline regex: {e}")),
+        "pidfile" => Ok(ProcessSelector::Pidfile(PathBuf::from(value))),
+        "user" => Ok(ProcessSelector::User(value.to_string())),
+        "cgroup" => Ok(ProcessSelector::Cgroup(value.to_string())),
+        _ => Err(format!(
+            "unknown selector kind '{kind}', expected pid, name, exe, cmdline, pidfile, user or cgroup"
+        )),
+    }
+}
+
+impl ProcessSelector {
+    /// Check whether the process `pid` under `proc_root` matches. Unreadable
+    /// or vanished processes simply do not match. `resolved` is looked up
+    /// once per pass: the uid for `User`, the PID in the file for `Pidfile`.
+    fn matches(&self, proc_root: &Path, pid: u32, resolved: Option<u32>) -> bool {
+        let dir = proc_root.join(pid.to_string());
+        match self {
+            Self::Pid(want) => *want == pid,
+            Self::Name(name) => fs::read_to_string(dir.join("comm"))
+                .map(|comm| comm_matches(comm.trim_end(), name))
+                .unwrap_or(false),
+            Self::Exe(path) => fs::read_link(dir.join("exe"))
+                .map(|exe| {
+                    let exe = exe.to_string_lossy();
+                    Path::new(exe.trim_end_matches(" (deleted)")) == path
+                })
+                .unwrap_or(false),
+            Self::Cmdline(re) => fs::read(dir.join("cmdline"))
+                .map(|raw| re.is_match(&cmdline_string(&raw)))
+                .unwrap_or(false),
+            Self::Pidfile(_) => resolved == Some(pid),
+            Self::User(_) => match (resolved, fs::read_to_string(dir.join("status"))) {
+                (Some(want), Ok(status)) => parse_status_uid(&status) == Some(want),
+                _ => false,
+            },
+            Self::Cgroup(want) => fs::read_to_string(dir.join("cgroup"))
+                .map(|content| {
+                    content.lines().any(|line| {
+                        line.splitn(3, ':')
+                            .nth(2)
+                            .is_some_and(|path| cgroup_contains(want, path))
+                    })
+                })
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Resolve every selector against the processes currently under `proc_root`.
+/// The agent itself is never matched: its own command line holds the
+/// selectors, so a `cmdline:` regex would otherwise always match it.
+/// Blocking; run it off the async runtime.
+pub fn resolve(selectors: &[ProcessSelector], proc_root: &Path) -> Vec<SelectorMatch> {
+    let own_pid = own_pid(proc_root);
+    let pids: Vec<u32> = list_pids(proc_root)
+        .into_iter()
+        .filter(|&pid| pid != own_pid)
+        .collect();
+    selectors
+        .iter()
+        .map(|selector| {
+            let resolved = match selector {
+                ProcessSelector::User(user) => resolve_uid(user),
+                ProcessSelector::Pidfile(path) => read_pidfile(path),
+                _ => None,
+            };
+            let mut matched: Vec<u32> = pids
+                .iter()
+                .copied()
+                .filter(|&pid| selector.matches(proc_root, pid, resolved))
+                .collect();
+            matched.sort_unstable();
+            SelectorMatch {
+                selector: selector.to_string(),
+                pids: matched,
+            }
+        })
+        .collect()
+}
+
+/// All numeric entries under `proc_root`.
+pub fn list_pids(proc_root: &Path) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect()
+}
+
+/// The agent's PID as seen under `proc_root`. `<proc_root>/self` resolves in
+/// the PID namespace of that proc mount, which differs from ours when the
+/// agent runs in a container with the host's /proc mounted.
+fn own_pid(proc_root: &Path) -> u32 {
+    fs::read_link(proc_root.join("self"))
+        .ok()
+        .and_then(|target| target.to_str()?.parse().ok())
+        .unwrap_or_else(std::process::id)
+}
+
+/// comm is truncated to 15 bytes by the kernel (TASK_COMM_LEN - 1).
+fn comm_matches(comm: &str, name: &str) -> bool {
+    comm == name || (name.len() > 15 && name.is_char_boundary(15) && comm == &name[..15])
+}
+
+fn cmdline_string(raw: &[u8]) -> String {
+    String::from_utf8_lossy(raw)
+        .trim_end_matches('\0')
+        .replace('\0', " ")
+}
+
+/// Real uid from the `Uid:` line of /proc/<pid>/status.
+fn parse_status_uid(status: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+}
+
+fn resolve_uid(user: &str) -> Option<u32> {
+    if let Ok(uid) = user.parse() {
+        return Some(uid);
+    }
+    nix::unistd::User::from_name(user)
+        .ok()
+        .flatten()
+        .map(|u| u.uid.as_raw())
+}
+
+fn cgroup_contains(want: &str, path: &str) -> bool {
+    let want = want.trim_end_matches('/');
+    path == want
+        || want.is_empty()
+        || path
+            .strip_prefix(want)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn read_pidfile(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_proc(dir: &Path, pid: u32, comm: &str, cmdline: &str, uid: u32, cgroup: &str) {
+        let p = dir.join(pid.to_string());
+        fs::create_dir_all(&p).unwrap();
+        fs::write(p.join("comm"), format!("{comm}\n")).unwrap();
+        fs::write(p.join("cmdline"), cmdline.replace(' ', "\0") + "\0").unwrap();
+        fs::write(
+            p.join("status"),
+            format!("Name:\t{comm}\nUid:\t{uid}\t{uid}\t{uid}\t{uid}\n"),
+        )
+        .unwrap();
+        fs::write(p.join("cgroup"), format!("0::{cgroup}\n")).unwrap();
+    }
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fake_proc(
+            dir.path(),
+            100,
+            "mysqld",
+            "/usr/sbin/mysqld --port=3306",
+            27,
+            "/system.slice/mysql.service",
+        );
+        fake_proc(
+            dir.path(),
+            200,
+            "mysqld",
+            "/usr/sbin/mysqld --port=3307",
+            27,
+            "/system.slice/mysql@replica.service",
+        );
+        fake_proc(dir.path(), 300, "bash", "-bash", 0, "/user.slice");
+        fs::create_dir_all(dir.path().join("self")).unwrap();
+        dir
+    }
+
+    fn pids_for(raw: &str, proc_root: &Path) -> Vec<u32> {
+        let selector = parse_selector(raw).unwrap();
+        resolve(&[selector], proc_root).remove(0).pids
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        assert!(matches!(
+            parse_selector("pid:42"),
+            Ok(ProcessSelector::Pid(42))
+        ));
+        assert!(parse_selector("name:mysqld").is_ok());
+        assert!(parse_selector("cmdline:(unclosed").is_err());
+        assert!(parse_selector("pid:abc").is_err());
+        assert!(parse_selector("nope:x").is_err());
+        assert!(parse_selector("name:").is_err());
+        assert!(parse_selector("mysqld").is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for raw in [
+            "name:mysqld",
+            "cmdline:--port=330[67]",
+            "cgroup:/system.slice",
+        ] {
+            assert_eq!(parse_selector(raw).unwrap().to_string(), raw);
+        }
+    }
+
+    #[test]
+    fn test_agent_itself_is_never_matched() {
+        let dir = fixture();
+        let own = std::process::id();
+        fake_proc(
+            dir.path(),
+            own,
+            "infra-health-ag",
+            "infra-health-agent --monitored-processes cmdline:mysqld.*--port=3306",
+            0,
+            "/system.slice/infra-health-agent.service",
+        );
+        assert_eq!(pids_for("cmdline:mysqld.*--port=3306", dir.path()), [100]);
+        assert_eq!(pids_for(&format!("pid:{own}"), dir.path()), [] as [u32; 0]);
+    }
+
+    #[test]
+    fn test_list_pids_skips_non_numeric() {
+        let dir = fixture();
+        let mut pids = list_pids(dir.path());
+        pids.sort_unstable();
+        assert_eq!(pids, vec![100, 200, 300]);
+    }
+
+    #[test]
+    fn test_resolve_by_name_and_cmdline() {
+        let dir = fixture();
+        assert_eq!(pids_for("name:mysqld", dir.path()), vec![100, 200]);
+        assert_eq!(pids_for("cmdline:--port=3307", dir.path()), vec![200]);
+        assert!(pids_for("name:postgres", dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_by_user_and_cgroup() {
+        let dir = fixture();
+        assert_eq!(pids_for("user:27", dir.path()), vec![100, 200]);
+        assert_eq!(
+            pids_for("cgroup:/system.slice/mysql.service", dir.path()),
+            vec![100]
+        );
+        assert_eq!(pids_for("cgroup:/system.slice", dir.path()), vec![100, 200]);
+    }
+
+    #[test]
+    fn test_resolve_by_pidfile() {
+        let dir = fixture();
+        let pidfile = dir.path().join("mysqld.pid");
+        fs::write(&pidfile, "200\n").unwrap();
+        assert_eq!(
+            pids_for(&format!("pidfile:{}", pidfile.display()), dir.path()),
+            vec![200]
+        );
+        // stale pidfile pointing at a process that is gone
+        fs::write(&pidfile, "999\n").unwrap();
+        assert!(pids_for(&format!("pidfile:{}", pidfile.display()), dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_monitored_processes_share_one_resolution() {
+        let dir = fixture();
+        let monitored = MonitoredProcesses::new(
+            vec![
+                parse_selector("name:mysqld").unwrap(),
+                parse_selector("cgroup:/system.slice").unwrap(),
+            ],
+            dir.path(),
+        );
+        let clone = monitored.clone();
+        assert!(clone.pids().is_empty());
+
+        monitored.refresh().await;
+        assert_eq!(clone.pids().into_iter().collect::<Vec<_>>(), vec![100, 200]);
+        assert_eq!(clone.matches().len(), 2);
+
+        // nothing changes until the next refresh
+        fs::remove_dir_all(dir.path().join("200")).unwrap();
+        assert_eq!(clone.pids().len(), 2);
+        monitored.refresh().await;
+        assert_eq!(clone.pids().into_iter().collect::<Vec<_>>(), vec![100]);
+    }
+
+    #[test]
+    fn test_comm_truncation() {
+        assert!(comm_matches("mysqld_exporter", "mysqld_exporter_v2"));
+        assert!(!comm_matches("mysqld", "mysql"));
+    }
+}